@@ -0,0 +1,218 @@
+//! Block move detection for line diffs
+//!
+//! When a function is relocated, a plain diff shows a large deletion in one
+//! place and an identical insertion in another. This module pairs those
+//! deletions and insertions up and reports them as moves, so renderers can
+//! show `moved from line 12` instead of repeating the whole block twice.
+
+use crate::char_diff::{DiffKind, diff_line_slices, split_lines};
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Minimum non-whitespace characters for a block to count as moved
+///
+/// Smaller blocks (a lone `}` or blank line) coincide too often to be
+/// meaningful moves.
+pub const DEFAULT_MIN_MOVE_CHARS: usize = 40;
+
+/// A block of lines deleted in one place and inserted unchanged in another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMove {
+    /// Zero-based line range in the expected (old) text
+    pub from_lines: Range<usize>,
+
+    /// Zero-based line range in the actual (new) text
+    pub to_lines: Range<usize>,
+
+    /// The moved lines, with terminators
+    pub text: String,
+}
+
+impl BlockMove {
+    /// Number of lines moved
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.from_lines.len()
+    }
+}
+
+/// Detect relocated blocks between `expected` and `actual`
+///
+/// Only lines the line diff reports as deleted or inserted are considered,
+/// and blocks are claimed largest-first so each line belongs to at most
+/// one move.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::block_moves::detect_block_moves;
+///
+/// let old = "fn helper() {\n    compute_everything();\n}\nfn main() {\n    run();\n}\n";
+/// let new = "fn main() {\n    run();\n}\nfn helper() {\n    compute_everything();\n}\n";
+///
+/// let moves = detect_block_moves(old, new, 20);
+/// assert_eq!(moves.len(), 1);
+/// assert_eq!(moves[0].from_lines, 0..3);
+/// assert_eq!(moves[0].to_lines, 3..6);
+/// ```
+#[must_use]
+pub fn detect_block_moves(expected: &str, actual: &str, min_chars: usize) -> Vec<BlockMove> {
+    let old_lines = split_lines(expected);
+    let new_lines = split_lines(actual);
+
+    // Which lines the diff reports as changed (candidates for a move)
+    let mut deleted = vec![false; old_lines.len()];
+    let mut inserted = vec![false; new_lines.len()];
+    let (mut oi, mut ni) = (0, 0);
+    for (kind, len) in diff_line_slices(&old_lines, &new_lines) {
+        match kind {
+            DiffKind::Equal => {
+                oi += len;
+                ni += len;
+            }
+            DiffKind::Delete => {
+                deleted[oi..oi + len].fill(true);
+                oi += len;
+            }
+            DiffKind::Insert => {
+                inserted[ni..ni + len].fill(true);
+                ni += len;
+            }
+        }
+    }
+
+    // Maximal common runs between deleted and inserted lines
+    let mut candidates: Vec<(usize, usize, usize, usize)> = Vec::new(); // (weight, old, new, len)
+    for (i, old_line) in old_lines.iter().enumerate() {
+        if !deleted[i] {
+            continue;
+        }
+        for (j, new_line) in new_lines.iter().enumerate() {
+            if !inserted[j] || old_line != new_line {
+                continue;
+            }
+            // Only start at the top of a run
+            if i > 0
+                && j > 0
+                && deleted[i - 1]
+                && inserted[j - 1]
+                && old_lines[i - 1] == new_lines[j - 1]
+            {
+                continue;
+            }
+            let len = (0..)
+                .take_while(|&k| {
+                    i + k < old_lines.len()
+                        && j + k < new_lines.len()
+                        && deleted[i + k]
+                        && inserted[j + k]
+                        && old_lines[i + k] == new_lines[j + k]
+                })
+                .count();
+            let weight = significant_chars(&old_lines[i..i + len]);
+            if weight >= min_chars {
+                candidates.push((weight, i, j, len));
+            }
+        }
+    }
+
+    // Claim the largest blocks first
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut moves = Vec::new();
+    for (_, i, j, len) in candidates {
+        // Skip runs overlapping a larger block that was already claimed
+        if (0..len).any(|k| !deleted[i + k] || !inserted[j + k]) {
+            continue;
+        }
+        deleted[i..i + len].fill(false);
+        inserted[j..j + len].fill(false);
+        moves.push(BlockMove {
+            from_lines: i..i + len,
+            to_lines: j..j + len,
+            text: old_lines[i..i + len].concat(),
+        });
+    }
+
+    moves.sort_by_key(|m| m.from_lines.start);
+    moves
+}
+
+/// Render a line diff with moved blocks collapsed to a single marker
+///
+/// Unchanged lines are printed as-is, deleted lines as `{-line-}` and
+/// inserted lines as `{+line+}`. A moved block is shown once at each end:
+/// `{~moved to line 40 (12 lines)~}` at its old position and
+/// `{~moved from line 3 (12 lines)~}` at its new position (1-based lines).
+#[must_use]
+pub fn format_with_moves(expected: &str, actual: &str, min_chars: usize) -> String {
+    let old_lines = split_lines(expected);
+    let new_lines = split_lines(actual);
+    let moves = detect_block_moves(expected, actual, min_chars);
+
+    let mut output = String::new();
+    let (mut oi, mut ni) = (0, 0);
+    for (kind, len) in diff_line_slices(&old_lines, &new_lines) {
+        match kind {
+            DiffKind::Equal => {
+                output.push_str(&old_lines[oi..oi + len].concat());
+                oi += len;
+                ni += len;
+            }
+            DiffKind::Delete => {
+                for (line, text) in old_lines.iter().enumerate().skip(oi).take(len) {
+                    match moves.iter().find(|m| m.from_lines.contains(&line)) {
+                        Some(m) if m.from_lines.start == line => {
+                            let _ = writeln!(
+                                output,
+                                "{{~moved to line {} ({} lines)~}}",
+                                m.to_lines.start + 1,
+                                m.line_count()
+                            );
+                        }
+                        Some(_) => {}
+                        None => push_marked(&mut output, "{-", text, "-}"),
+                    }
+                }
+                oi += len;
+            }
+            DiffKind::Insert => {
+                for (line, text) in new_lines.iter().enumerate().skip(ni).take(len) {
+                    match moves.iter().find(|m| m.to_lines.contains(&line)) {
+                        Some(m) if m.to_lines.start == line => {
+                            let _ = writeln!(
+                                output,
+                                "{{~moved from line {} ({} lines)~}}",
+                                m.from_lines.start + 1,
+                                m.line_count()
+                            );
+                        }
+                        Some(_) => {}
+                        None => push_marked(&mut output, "{+", text, "+}"),
+                    }
+                }
+                ni += len;
+            }
+        }
+    }
+    output
+}
+
+/// Wrap a line's content in markers, keeping its terminator outside
+fn push_marked(output: &mut String, open: &str, line: &str, close: &str) {
+    let content = line.strip_suffix('\n').unwrap_or(line);
+    output.push_str(open);
+    output.push_str(content);
+    output.push_str(close);
+    if line.ends_with('\n') {
+        output.push('\n');
+    }
+}
+
+/// Count non-whitespace characters across lines
+fn significant_chars(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .flat_map(|line| line.chars())
+        .filter(|c| !c.is_whitespace())
+        .count()
+}
@@ -0,0 +1,227 @@
+//! Byte order mark detection, stripping and restoration
+//!
+//! A leading BOM is file metadata, not content: a search string that starts
+//! at line 1 never contains it, so matching should ignore it and writing
+//! should put it back. [`FormatFingerprint`] records the BOM together with
+//! the file's line endings so an edited file can be written in its original
+//! format.
+
+use crate::encoding::TextEncoding;
+use crate::line_endings::{LineEndingStyle, analyze_line_endings, normalize_line_endings};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The BOM as it appears in decoded text
+pub const BOM_CHAR: char = '\u{FEFF}';
+
+// ============================================================================
+// BYTE ORDER MARKS
+// ============================================================================
+
+/// A Unicode byte order mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Bom {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Bom {
+    /// Every BOM, longest first where one is a prefix of another
+    ///
+    /// UTF-32LE's `FF FE 00 00` starts with UTF-16LE's `FF FE`.
+    pub const ALL: [Bom; 5] = [
+        Bom::Utf8,
+        Bom::Utf32Le,
+        Bom::Utf32Be,
+        Bom::Utf16Le,
+        Bom::Utf16Be,
+    ];
+
+    /// Encoding the BOM announces
+    #[must_use]
+    pub fn encoding(&self) -> TextEncoding {
+        match self {
+            Self::Utf8 => TextEncoding::Utf8,
+            Self::Utf16Le => TextEncoding::Utf16Le,
+            Self::Utf16Be => TextEncoding::Utf16Be,
+            Self::Utf32Le => TextEncoding::Utf32Le,
+            Self::Utf32Be => TextEncoding::Utf32Be,
+        }
+    }
+
+    /// BOM for `encoding`, if it has one
+    #[must_use]
+    pub fn for_encoding(encoding: TextEncoding) -> Option<Self> {
+        Self::ALL.into_iter().find(|bom| bom.encoding() == encoding)
+    }
+
+    /// The BOM's bytes
+    #[must_use]
+    pub fn bytes(&self) -> &'static [u8] {
+        self.encoding().bom()
+    }
+
+    /// Encoding name, e.g. `"UTF-16LE"`
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.encoding().name()
+    }
+}
+
+impl std::fmt::Display for Bom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} BOM", self.name())
+    }
+}
+
+/// Detect the BOM at the start of `bytes`
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::bom::{detect_bom, Bom};
+///
+/// assert_eq!(detect_bom(b"\xEF\xBB\xBFfn main() {}"), Some(Bom::Utf8));
+/// assert_eq!(detect_bom(b"\xFF\xFE\x00\x00"), Some(Bom::Utf32Le));
+/// assert_eq!(detect_bom(b"fn main() {}"), None);
+/// ```
+#[must_use]
+pub fn detect_bom(bytes: &[u8]) -> Option<Bom> {
+    Bom::ALL
+        .into_iter()
+        .find(|bom| bytes.starts_with(bom.bytes()))
+}
+
+/// Split a leading BOM off `bytes`
+#[must_use]
+pub fn strip_bom(bytes: &[u8]) -> (Option<Bom>, &[u8]) {
+    match detect_bom(bytes) {
+        Some(bom) => (Some(bom), &bytes[bom.bytes().len()..]),
+        None => (None, bytes),
+    }
+}
+
+/// Prepend `bom` to `bytes` unless it is already there
+#[must_use]
+pub fn restore_bom(bom: Option<Bom>, bytes: &[u8]) -> Vec<u8> {
+    match bom {
+        Some(bom) if !bytes.starts_with(bom.bytes()) => [bom.bytes(), bytes].concat(),
+        _ => bytes.to_vec(),
+    }
+}
+
+// ============================================================================
+// DECODED TEXT
+// ============================================================================
+
+/// Split a leading U+FEFF off decoded text
+///
+/// `std::fs::read_to_string` keeps a UTF-8 BOM as U+FEFF at the start of the
+/// string; this removes it for matching.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::bom::{restore_bom_str, strip_bom_str};
+///
+/// let (had_bom, body) = strip_bom_str("\u{FEFF}fn main() {}");
+/// assert!(had_bom);
+/// assert_eq!(body, "fn main() {}");
+/// assert_eq!(restore_bom_str(had_bom, body), "\u{FEFF}fn main() {}");
+/// ```
+#[must_use]
+pub fn strip_bom_str(text: &str) -> (bool, &str) {
+    match text.strip_prefix(BOM_CHAR) {
+        Some(body) => (true, body),
+        None => (false, text),
+    }
+}
+
+/// Prepend U+FEFF to `text` when `had_bom` is set and it is not already there
+#[must_use]
+pub fn restore_bom_str(had_bom: bool, text: &str) -> String {
+    if had_bom && !text.starts_with(BOM_CHAR) {
+        format!("{BOM_CHAR}{text}")
+    } else {
+        text.to_string()
+    }
+}
+
+// ============================================================================
+// FORMAT FINGERPRINT
+// ============================================================================
+
+/// How a file is laid out on disk, apart from its content
+///
+/// Taken from the original file before an edit and applied to the edited
+/// text before writing, so the edit does not drop the BOM or switch line
+/// endings.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::bom::FormatFingerprint;
+///
+/// let original = "\u{FEFF}fn main() {\r\n    run();\r\n}\r\n";
+/// let format = FormatFingerprint::detect(original);
+///
+/// // Match against the body; the search never contains the BOM
+/// let body = format.strip(original);
+/// assert!(body.starts_with("fn main() {"));
+///
+/// // The replacement was written with LF endings
+/// let edited = body.replace("    run();\r\n", "    run();\n    done();\n");
+/// assert_eq!(
+///     format.apply(&edited),
+///     "\u{FEFF}fn main() {\r\n    run();\r\n    done();\r\n}\r\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatFingerprint {
+    /// Whether the text starts with U+FEFF
+    pub has_bom: bool,
+
+    /// Predominant line ending, or `None` for single-line text
+    pub line_ending: Option<LineEndingStyle>,
+
+    /// Whether several line-ending styles are present
+    pub mixed_line_endings: bool,
+}
+
+impl FormatFingerprint {
+    /// Fingerprint decoded file content
+    #[must_use]
+    pub fn detect(content: &str) -> Self {
+        let (has_bom, body) = strip_bom_str(content);
+        let endings = analyze_line_endings(body);
+        Self {
+            has_bom,
+            line_ending: (endings.total_count > 0).then_some(endings.style),
+            mixed_line_endings: endings.has_mixed,
+        }
+    }
+
+    /// Content without the BOM, for matching
+    #[must_use]
+    pub fn strip<'a>(&self, content: &'a str) -> &'a str {
+        strip_bom_str(content).1
+    }
+
+    /// Text in this format: BOM restored, line endings converted
+    ///
+    /// Files with mixed line endings are left as they are; there is no single
+    /// style to convert to.
+    #[must_use]
+    pub fn apply(&self, text: &str) -> String {
+        let (_, body) = strip_bom_str(text);
+        let body = match self.line_ending {
+            Some(style) if !self.mixed_line_endings => normalize_line_endings(body, style),
+            _ => body.to_string(),
+        };
+        restore_bom_str(self.has_bom, &body)
+    }
+}
@@ -4,37 +4,99 @@
 //! identify and fix invisible character differences (tabs, spaces, line endings,
 //! zero-width Unicode, encoding issues, etc.)
 
+use crate::char_diff::{
+    ByteToken, DiffKind, decode_bytes, diff_line_slices, render_tokens, split_lines,
+};
+use crate::confusables::{Confusable, confusable_pairs};
+use crate::escaping::{EscapeMismatch, diagnose_escaping};
+use crate::smart_punctuation::{ascii_equivalent, asciify};
+use crate::terminal::{
+    TerminalArtefact, detect_terminal_artefacts, render_terminal, uses_cr_line_endings,
+};
+use crate::tool_artefacts::{ToolArtefact, detect_artefacts, strip_artefact};
 use lru::LruCache;
 use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::num::NonZeroUsize;
-use unicode_normalization::UnicodeNormalization;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::OnceLock;
+use unicode_normalization::{UnicodeNormalization, is_nfc, is_nfd};
 
 // ============================================================================
 // CONSTANTS
 // ============================================================================
 
-/// Zero-width Unicode characters that are invisible but affect string matching
-const ZERO_WIDTH_CHARS: &[u32] = &[
-    0x200B, // Zero-width space
-    0x200C, // Zero-width non-joiner
-    0x200D, // Zero-width joiner
-    0xFEFF, // Zero-width no-break space (BOM)
+/// Default-ignorable code point ranges (Unicode 15 `Default_Ignorable_Code_Point`)
+///
+/// These render as nothing at all but still affect string matching:
+/// zero-width spaces and joiners, soft hyphens, variation selectors, tags, etc.
+const DEFAULT_IGNORABLE_RANGES: &[(u32, u32)] = &[
+    (0x00AD, 0x00AD),   // Soft hyphen
+    (0x034F, 0x034F),   // Combining grapheme joiner
+    (0x061C, 0x061C),   // Arabic letter mark
+    (0x115F, 0x1160),   // Hangul choseong/jungseong fillers
+    (0x17B4, 0x17B5),   // Khmer inherent vowels
+    (0x180B, 0x180F),   // Mongolian variation selectors, vowel separator
+    (0x200B, 0x200F),   // ZWSP, ZWNJ, ZWJ, LRM, RLM
+    (0x202A, 0x202E),   // Bidi embeddings and overrides
+    (0x2060, 0x206F),   // Word joiner, invisible operators, bidi isolates
+    (0x3164, 0x3164),   // Hangul filler
+    (0xFE00, 0xFE0F),   // Variation selectors 1-16
+    (0xFEFF, 0xFEFF),   // Zero-width no-break space (BOM)
+    (0xFFA0, 0xFFA0),   // Halfwidth Hangul filler
+    (0xFFF0, 0xFFF8),   // Unassigned specials
+    (0x1BCA0, 0x1BCA3), // Shorthand format controls
+    (0x1D173, 0x1D17A), // Musical symbol format controls
+    (0xE0000, 0xE0FFF), // Tags and variation selectors 17-256
 ];
 
-/// Global LRU cache for analysis results (100 most recent)
-static ANALYSIS_CACHE: std::sync::LazyLock<Mutex<LruCache<String, CharCodeData>>> =
-    std::sync::LazyLock::new(|| {
-        // SAFETY: 100 is a non-zero compile-time constant
-        Mutex::new(LruCache::new(unsafe { NonZeroUsize::new_unchecked(100) }))
-    });
+/// Bidirectional formatting characters (Unicode 15, UAX #9)
+///
+/// These reorder how text is displayed without changing how it is parsed,
+/// which is what "Trojan Source" (CVE-2021-42574) attacks exploit.
+const BIDI_CONTROL_CHARS: &[u32] = &[
+    0x061C, // Arabic letter mark
+    0x200E, // Left-to-right mark
+    0x200F, // Right-to-left mark
+    0x202A, // Left-to-right embedding
+    0x202B, // Right-to-left embedding
+    0x202C, // Pop directional formatting
+    0x202D, // Left-to-right override
+    0x202E, // Right-to-left override
+    0x2066, // Left-to-right isolate
+    0x2067, // Right-to-left isolate
+    0x2068, // First strong isolate
+    0x2069, // Pop directional isolate
+];
+
+/// Version of the JSON document produced by [`CharCodeData::to_json`]
+///
+/// Bumped whenever a field is removed, renamed or changes meaning; adding
+/// fields does not change the version (they default when missing).
+///
+/// - 1: first versioned document
+/// - 2: `report` labels carry `U+XXXX` and Unicode names; `has_composed` /
+///   `has_decomposed` mean "not NFD" / "not NFC" rather than "equals its
+///   NFC" / "differs from its NFD".
+pub const ANALYSIS_SCHEMA_VERSION: u32 = 2;
+
+/// Codes at or above this value stand for undecodable bytes (`base + byte`),
+/// safely outside the Unicode range
+const INVALID_BYTE_BASE: u32 = 0x11_0000;
+
+/// Default number of analyses kept by the global cache
+const DEFAULT_CACHE_CAPACITY: usize = 100;
+
+/// Default per-entry input limit (1 MiB); larger pairs are analyzed uncached
+const DEFAULT_CACHE_MAX_ENTRY_BYTES: usize = 1 << 20;
 
 // ============================================================================
 // CORE DATA STRUCTURES
 // ============================================================================
 
 /// Comprehensive character code analysis result
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CharCodeData {
     /// Basic report: "code:count[display],..." format
     pub report: String,
@@ -54,16 +116,45 @@ pub struct CharCodeData {
     /// Detected encoding issues
     pub encoding_issues: Vec<EncodingIssue>,
 
+    /// Bidi control characters anywhere in either text (not just the diff)
+    #[serde(default)]
+    pub security_issues: Vec<SecurityIssue>,
+
     /// Character distribution comparison
     pub distribution: CharDistribution,
 
     /// Unicode normalization analysis
     pub unicode_analysis: UnicodeAnalysis,
 
-    /// Zero-width character detection
+    /// Default-ignorable (invisible) character detection
     pub has_zero_width: bool,
 
-    /// Smart fix suggestion
+    /// Look-alike (TR39 confusable) substitutions between the diffs
+    #[serde(default)]
+    pub confusables: Vec<Confusable>,
+
+    /// Typographic punctuation explaining the whole diff (empty if the diffs
+    /// still differ once punctuation is ASCII-fied)
+    #[serde(default)]
+    pub smart_punctuation: Vec<char>,
+
+    /// Escaping difference (e.g. literal `\n` vs newline) explaining the whole mismatch
+    #[serde(default)]
+    pub escaping: Option<EscapeMismatch>,
+
+    /// Tool-output artefacts (line numbers, fences, ...) in expected but not in actual
+    #[serde(default)]
+    pub tool_artefacts: Vec<ToolArtefact>,
+
+    /// Terminal escape sequences and overwrites in expected but not in actual
+    #[serde(default)]
+    pub terminal_artefacts: Vec<TerminalArtefact>,
+
+    /// Every detected issue, most important first
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+
+    /// Smart fix suggestion (top finding, plus a breakdown when several contribute)
     pub suggestion: Option<String>,
 
     /// Visual diff with inline codes
@@ -71,34 +162,64 @@ pub struct CharCodeData {
 }
 
 /// Semantic grouping of character types
-#[derive(Debug, Clone, Default)]
+///
+/// Entries are `(code, count)` pairs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct CharCodeClassification {
-    pub whitespace: Vec<(u32, usize)>,   // spaces, tabs, nbsp
-    pub line_endings: Vec<(u32, usize)>, // CR, LF
+    pub whitespace: Vec<(u32, usize)>,   // spaces, tabs, nbsp, em space
+    pub line_endings: Vec<(u32, usize)>, // CR, LF, NEL, LS, PS
+    pub invisible: Vec<(u32, usize)>,    // zero-width, SHY, variation selectors
     pub printable: Vec<(u32, usize)>,    // regular printable chars
     pub control: Vec<(u32, usize)>,      // control chars
     pub unicode: Vec<(u32, usize)>,      // non-ASCII
+    pub invalid: Vec<(u32, usize)>,      // undecodable bytes (see analyze_bytes)
 }
 
 /// Common whitespace/formatting problems
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum WhitespaceIssue {
     TabsVsSpaces,       // Mixed tabs and spaces
     MixedLineEndings,   // Both CR and LF present
     ExtraSpaces,        // More than 3 consecutive spaces
     TrailingWhitespace, // Whitespace at end of lines
+    ExoticSpaces,       // Unicode spaces other than SPACE/TAB (NBSP, EM SPACE, ...)
+}
+
+/// Security problems: text that displays differently from how it compiles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityIssue {
+    BidiOverride,   // LRO/RLO (U+202D, U+202E)
+    BidiEmbedding,  // LRE/RLE (U+202A, U+202B)
+    BidiIsolate,    // LRI/RLI/FSI (U+2066–U+2068)
+    BidiMark,       // LRM/RLM/ALM (U+200E, U+200F, U+061C)
+    UnbalancedBidi, // Embedding/override/isolate not closed on its line, or stray PDF/PDI
+}
+
+impl SecurityIssue {
+    /// Whether the issue can reorder how code displays (Trojan Source)
+    ///
+    /// Directional marks are ordinary in right-to-left text and only nudge
+    /// the direction of neutral characters next to them, so they are not.
+    #[must_use]
+    pub fn is_critical(&self) -> bool {
+        !matches!(self, Self::BidiMark)
+    }
 }
 
 /// Encoding-related problems
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum EncodingIssue {
     Utf16Surrogate,  // UTF-16 surrogate in UTF-8 context
     ReplacementChar, // U+FFFD � character
     ByteOrderMark,   // U+FEFF BOM character
+    InvalidUtf8,     // Bytes that are not valid UTF-8
 }
 
 /// Comparison of character distributions
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct CharDistribution {
     pub only_in_expected: Vec<(u32, usize)>, // Chars only in search string
     pub only_in_actual: Vec<(u32, usize)>,   // Chars only in found string
@@ -106,11 +227,118 @@ pub struct CharDistribution {
 }
 
 /// Unicode normalization status
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UnicodeAnalysis {
     pub has_composed: bool,           // Contains composed chars (é)
     pub has_decomposed: bool,         // Contains decomposed chars (e + ´)
     pub normalization_mismatch: bool, // NFC normalized would match
+
+    /// NFKC normalized would match, but NFC would not (fullwidth, ligatures, ...)
+    #[serde(default)]
+    pub compatibility_mismatch: bool,
+
+    /// Compatibility mappings applied to the differing characters
+    #[serde(default)]
+    pub compatibility_mappings: Vec<CompatibilityMapping>,
+}
+
+/// A character NFKC replaces with its compatibility equivalent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CompatibilityMapping {
+    pub from: char,
+
+    /// NFKC form, e.g. "fi" for U+FB01
+    pub to: String,
+}
+
+impl CompatibilityMapping {
+    /// Describe the mapping, e.g. `'ﬁ' U+FB01 → "fi"`
+    #[must_use]
+    pub fn describe(&self) -> String {
+        format!(
+            "'{}' U+{:04X} → \"{}\"",
+            self.from,
+            u32::from(self.from),
+            self.to
+        )
+    }
+}
+
+/// How serious a finding is, lowest first
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,     // Cosmetic; rarely the whole story
+    Warning,  // Likely cause of the mismatch
+    Error,    // The file or search text is damaged
+    Critical, // Security risk, whatever the mismatch
+}
+
+/// What a [`Finding`] is about
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    Security(SecurityIssue),
+    ZeroWidth,
+    Confusables,
+    SmartPunctuation,
+    Escaping,
+    ToolArtefact(ToolArtefact),
+    TerminalArtefacts,
+    NormalizationMismatch,
+    CompatibilityNormalization,
+    Whitespace(WhitespaceIssue),
+    Encoding(EncodingIssue),
+}
+
+/// One detected issue, scored so several can be ranked against each other
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Finding {
+    pub kind: FindingKind,
+
+    pub severity: Severity,
+
+    /// How likely the issue is a real cause of the mismatch (0.0-1.0)
+    pub confidence: f64,
+
+    /// Diff characters attributable to the issue
+    pub explained_chars: usize,
+
+    /// Share of the diff the issue explains (0.0-1.0)
+    pub explained: f64,
+
+    /// Actionable fix for this issue alone
+    pub suggestion: String,
+}
+
+impl Finding {
+    /// Short label, e.g. "tabs vs spaces"
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match &self.kind {
+            FindingKind::Security(SecurityIssue::UnbalancedBidi) => "unbalanced bidi controls",
+            FindingKind::Security(_) => "bidi control characters",
+            FindingKind::ZeroWidth => "zero-width characters",
+            FindingKind::Confusables => "look-alike characters",
+            FindingKind::SmartPunctuation => "smart punctuation",
+            FindingKind::Escaping => "escaping",
+            FindingKind::ToolArtefact(artefact) => artefact.label(),
+            FindingKind::TerminalArtefacts => "terminal artefacts",
+            FindingKind::NormalizationMismatch => "Unicode normalization",
+            FindingKind::CompatibilityNormalization => "compatibility characters",
+            FindingKind::Whitespace(WhitespaceIssue::TabsVsSpaces) => "tabs vs spaces",
+            FindingKind::Whitespace(WhitespaceIssue::MixedLineEndings) => "mixed line endings",
+            FindingKind::Whitespace(WhitespaceIssue::ExtraSpaces) => "extra spaces",
+            FindingKind::Whitespace(WhitespaceIssue::TrailingWhitespace) => "trailing whitespace",
+            FindingKind::Whitespace(WhitespaceIssue::ExoticSpaces) => "non-ASCII spaces",
+            FindingKind::Encoding(EncodingIssue::ReplacementChar) => "replacement characters",
+            FindingKind::Encoding(EncodingIssue::ByteOrderMark) => "byte order mark",
+            FindingKind::Encoding(EncodingIssue::Utf16Surrogate) => "UTF-16 surrogates",
+            FindingKind::Encoding(EncodingIssue::InvalidUtf8) => "invalid UTF-8",
+        }
+    }
 }
 
 // ============================================================================
@@ -124,90 +352,175 @@ impl CharCodeData {
     /// between expected and actual strings, providing detailed diagnostic information.
     #[must_use]
     pub fn analyze(expected: &str, actual: &str) -> Self {
-        // Try cache first
-        let cache_key = format!("{expected}|{actual}");
-        {
-            let mut cache = ANALYSIS_CACHE.lock();
-            if let Some(cached) = cache.get(&cache_key) {
-                return cached.clone();
-            }
-        }
+        Self::analyze_with(expected, actual, CachePolicy::Use)
+    }
 
-        // Perform analysis
-        let result = Self::analyze_uncached(expected, actual);
+    /// Analyze with an explicit cache policy for this call
+    ///
+    /// See [`get_analysis_cache`] to configure the cache or read its metrics.
+    #[must_use]
+    pub fn analyze_with(expected: &str, actual: &str, policy: CachePolicy) -> Self {
+        get_analysis_cache().analyze(expected, actual, policy)
+    }
 
-        // Cache result
+    /// Analyze byte strings that may contain invalid UTF-8
+    ///
+    /// Valid input is analyzed exactly like [`CharCodeData::analyze`].
+    /// Otherwise undecodable bytes are tracked individually: they are
+    /// reported as `\xHH`, classified as invalid, and raise
+    /// [`EncodingIssue::InvalidUtf8`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kodegen_utils::char_analysis::{CharCodeData, EncodingIssue};
+    ///
+    /// let analysis = CharCodeData::analyze_bytes(b"caf\xe9", "café".as_bytes());
+    /// assert!(analysis.encoding_issues.contains(&EncodingIssue::InvalidUtf8));
+    /// assert!(analysis.report.contains("[\\xe9]"));
+    /// ```
+    #[must_use]
+    pub fn analyze_bytes(expected: &[u8], actual: &[u8]) -> Self {
+        if let (Ok(expected), Ok(actual)) =
+            (std::str::from_utf8(expected), std::str::from_utf8(actual))
         {
-            let mut cache = ANALYSIS_CACHE.lock();
-            cache.put(cache_key, result.clone());
+            return Self::analyze(expected, actual);
         }
 
-        result
+        let exp_codes = byte_codes(expected);
+        let act_codes = byte_codes(actual);
+        Self::analyze_codes(
+            &exp_codes,
+            &act_codes,
+            &String::from_utf8_lossy(expected),
+            &String::from_utf8_lossy(actual),
+        )
     }
 
     fn analyze_uncached(expected: &str, actual: &str) -> Self {
+        let exp_codes: Vec<u32> = expected.chars().map(u32::from).collect();
+        let act_codes: Vec<u32> = actual.chars().map(u32::from).collect();
+        Self::analyze_codes(&exp_codes, &act_codes, expected, actual)
+    }
+
+    /// Core analysis over code sequences
+    ///
+    /// `expected_text` / `actual_text` are the (lossily) decoded strings,
+    /// used where a real string is needed (Unicode normalization).
+    fn analyze_codes(
+        exp_codes: &[u32],
+        act_codes: &[u32],
+        expected_text: &str,
+        actual_text: &str,
+    ) -> Self {
         // Step 1: Find common boundaries
-        let (prefix_len, suffix_len) = find_common_boundaries(expected, actual);
+        let (prefix_len, suffix_len) = find_common_boundaries(exp_codes, act_codes);
 
         // Step 2: Extract diffs using character indices
-        let exp_chars: Vec<char> = expected.chars().collect();
-        let act_chars: Vec<char> = actual.chars().collect();
+        let exp_diff_codes = &exp_codes[prefix_len..exp_codes.len().saturating_sub(suffix_len)];
+        let act_diff_codes = &act_codes[prefix_len..act_codes.len().saturating_sub(suffix_len)];
 
-        let exp_diff_chars = &exp_chars[prefix_len..exp_chars.len().saturating_sub(suffix_len)];
-        let act_diff_chars = &act_chars[prefix_len..act_chars.len().saturating_sub(suffix_len)];
-
-        let expected_diff: String = exp_diff_chars.iter().collect();
-        let actual_diff: String = act_diff_chars.iter().collect();
+        let expected_diff = render_codes(exp_diff_codes);
+        let actual_diff = render_codes(act_diff_codes);
 
         // Step 3: Count character codes in combined diff
-        let mut full_diff = String::new();
-        full_diff.push_str(&expected_diff);
-        full_diff.push_str(&actual_diff);
-
         let mut codes: HashMap<u32, usize> = HashMap::new();
-        for ch in full_diff.chars() {
-            *codes.entry(ch as u32).or_insert(0) += 1;
+        for &code in exp_diff_codes.iter().chain(act_diff_codes) {
+            *codes.entry(code).or_insert(0) += 1;
         }
 
         // Step 4: Generate basic report
         let report = format_char_code_report(&codes);
         let unique_count = codes.len();
-        let diff_length = full_diff.chars().count();
+        let diff_length = exp_diff_codes.len() + act_diff_codes.len();
 
         // Step 5: Semantic classification
         let classification = classify_characters(&codes);
 
-        // Step 6: Detect whitespace issues
-        let whitespace_issues = detect_whitespace_issues(&codes, &expected_diff, &actual_diff);
+        // Step 6: Detect whitespace issues (whitespace at the end of a diff is
+        // only trailing when a line ends right after it)
+        let line_end_at =
+            |codes: &[u32], index: usize| codes.get(index).is_none_or(|&c| c == 10 || c == 13);
+        let trailing_whitespace = trailing_whitespace_len(
+            &expected_diff,
+            line_end_at(exp_codes, exp_codes.len().saturating_sub(suffix_len)),
+        ) + trailing_whitespace_len(
+            &actual_diff,
+            line_end_at(act_codes, act_codes.len().saturating_sub(suffix_len)),
+        );
+        let whitespace_issues = detect_whitespace_issues(&codes, trailing_whitespace);
 
         // Step 7: Detect encoding issues
         let encoding_issues = detect_encoding_issues(&codes);
 
+        // Step 7b: Detect bidi controls in the full search text and file region
+        let security_issues = detect_security_issues(exp_codes, act_codes);
+
         // Step 8: Character distribution comparison
-        let distribution = compare_distribution(&expected_diff, &actual_diff);
+        let distribution = compare_distribution(exp_diff_codes, act_diff_codes);
 
         // Step 9: Unicode normalization analysis
-        let unicode_analysis = analyze_unicode(expected, actual);
-
-        // Step 10: Zero-width detection
-        let has_zero_width = ZERO_WIDTH_CHARS
-            .iter()
-            .any(|&code| codes.contains_key(&code));
-
-        // Step 11: Generate smart suggestion
-        let suggestion = generate_suggestion(
-            &whitespace_issues,
-            &encoding_issues,
-            has_zero_width,
-            &unicode_analysis,
+        let unicode_analysis =
+            analyze_unicode(expected_text, actual_text, &expected_diff, &actual_diff);
+
+        // Step 10: Invisible (default-ignorable) character detection
+        let has_zero_width = codes.keys().any(|&code| is_default_ignorable(code));
+
+        // Step 11: Homoglyph detection
+        let confusables = confusable_pairs(&expected_diff, &actual_diff);
+
+        // Step 11b: Smart punctuation (curly quotes, dashes, ellipsis)
+        let smart_punctuation = detect_smart_punctuation(&expected_diff, &actual_diff);
+
+        // Step 11c: Escaping mismatch (JSON, Rust/C, HTML entities, URL)
+        let escaping = diagnose_escaping(expected_text, actual_text);
+
+        // Step 11d: Artefacts copied from tool output
+        let actual_artefacts = detect_artefacts(actual_text);
+        let tool_artefacts: Vec<ToolArtefact> = detect_artefacts(expected_text)
+            .into_iter()
+            .filter(|artefact| !actual_artefacts.contains(artefact))
+            .collect();
+
+        // Step 11e: Terminal escape sequences and overwrites (a bare CR is a
+        // line ending, not an overwrite, when the actual text ends lines with it)
+        let actual_terminal = detect_terminal_artefacts(actual_text);
+        let actual_cr_endings = uses_cr_line_endings(actual_text);
+        let terminal_artefacts: Vec<TerminalArtefact> = detect_terminal_artefacts(expected_text)
+            .into_iter()
+            .filter(|artefact| !actual_terminal.contains(artefact))
+            .filter(|artefact| {
+                !(actual_cr_endings && *artefact == TerminalArtefact::CarriageReturn)
+            })
+            .collect();
+
+        // Step 12: Rank findings and generate smart suggestion
+        let findings = rank_findings(
+            &DetectedIssues {
+                security: &security_issues,
+                whitespace: &whitespace_issues,
+                encoding: &encoding_issues,
+                has_zero_width,
+                confusables: &confusables,
+                smart_punctuation: &smart_punctuation,
+                escaping: escaping.as_ref(),
+                tool_artefacts: &tool_artefacts,
+                terminal_artefacts: &terminal_artefacts,
+                expected_text,
+                actual_text,
+                unicode: &unicode_analysis,
+            },
+            &codes,
+            diff_length,
+            trailing_whitespace,
         );
+        let suggestion = generate_suggestion(&findings, diff_length);
 
-        // Step 12: Visual diff with inline codes
+        // Step 13: Visual diff with inline codes
         let visual_diff_with_codes = format_visual_diff_with_codes(
-            expected,
-            actual,
-            &expected_diff,
-            &actual_diff,
+            exp_codes,
+            exp_diff_codes,
+            act_diff_codes,
             prefix_len,
             suffix_len,
         );
@@ -219,9 +532,16 @@ impl CharCodeData {
             classification,
             whitespace_issues,
             encoding_issues,
+            security_issues,
             distribution,
             unicode_analysis,
             has_zero_width,
+            confusables,
+            smart_punctuation,
+            escaping,
+            tool_artefacts,
+            terminal_artefacts,
+            findings,
             suggestion,
             visual_diff_with_codes,
         }
@@ -233,10 +553,7 @@ impl CharCodeData {
 // ============================================================================
 
 /// Find common prefix and suffix lengths (in character counts, not bytes)
-fn find_common_boundaries(a: &str, b: &str) -> (usize, usize) {
-    let a_chars: Vec<char> = a.chars().collect();
-    let b_chars: Vec<char> = b.chars().collect();
-
+fn find_common_boundaries(a_chars: &[u32], b_chars: &[u32]) -> (usize, usize) {
     // Find prefix length
     let prefix_len = a_chars
         .iter()
@@ -257,8 +574,38 @@ fn find_common_boundaries(a: &str, b: &str) -> (usize, usize) {
     (prefix_len, suffix_len)
 }
 
+/// Decode bytes into codes, mapping undecodable bytes above the Unicode range
+fn byte_codes(bytes: &[u8]) -> Vec<u32> {
+    decode_bytes(bytes)
+        .into_iter()
+        .map(|token| match token {
+            ByteToken::Char(c) => u32::from(c),
+            ByteToken::Invalid(byte) => INVALID_BYTE_BASE + u32::from(byte),
+        })
+        .collect()
+}
+
+/// Render codes as text, showing undecodable bytes as `\xHH`
+fn render_codes(codes: &[u32]) -> String {
+    let tokens: Vec<ByteToken> = codes
+        .iter()
+        .map(|&code| match invalid_byte(code) {
+            Some(byte) => ByteToken::Invalid(byte),
+            None => ByteToken::Char(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)),
+        })
+        .collect();
+    render_tokens(&tokens)
+}
+
+/// The original byte if `code` stands for an undecodable byte
+fn invalid_byte(code: u32) -> Option<u8> {
+    code.checked_sub(INVALID_BYTE_BASE)
+        .and_then(|byte| u8::try_from(byte).ok())
+}
+
 /// Format character codes as "code:count[display],code:count[display],..."
-/// Sorted by character code in ascending order
+/// Sorted by character code in ascending order; undecodable bytes use a
+/// `0xHH` code so they can't be mistaken for characters
 fn format_char_code_report(codes: &HashMap<u32, usize>) -> String {
     let mut entries: Vec<_> = codes.iter().collect();
     entries.sort_by_key(|(code, _)| *code); // Sort by code ascending
@@ -267,7 +614,10 @@ fn format_char_code_report(codes: &HashMap<u32, usize>) -> String {
         .iter()
         .map(|(code, count)| {
             let display = format_char_display(**code);
-            format!("{code}:{count}[{display}]")
+            match invalid_byte(**code) {
+                Some(byte) => format!("0x{byte:02x}:{count}[{display}]"),
+                None => format!("{code}:{count}[{display}]"),
+            }
         })
         .collect::<Vec<_>>()
         .join(",")
@@ -275,30 +625,116 @@ fn format_char_code_report(codes: &HashMap<u32, usize>) -> String {
 
 /// Format a single character code for display
 /// - Printable ASCII (32-126): show the character itself
-/// - Non-printable (<32, 127+): show as \xHH hex escape
+/// - Other code points: `U+XXXX NAME` (see [`format_char_label`])
+/// - Undecodable bytes: show the raw byte as \xHH
 fn format_char_display(code: u32) -> String {
+    if let Some(byte) = invalid_byte(code) {
+        return format!("\\x{byte:02x}");
+    }
     match code {
-        32..=126 => {
-            char::from_u32(code).map_or_else(|| format!("\\x{code:02x}"), |c| c.to_string())
-        }
-        _ => format!("\\x{code:02x}"),
+        32..=126 => char::from_u32(code).map_or_else(|| format_char_label(code), |c| c.to_string()),
+        _ => format_char_label(code),
     }
 }
 
-/// Get human-readable name for common character codes
-fn format_char_name(code: u32) -> &'static str {
-    match code {
+/// Format a code as `U+XXXX NAME`, e.g. `U+00A0 NO-BREAK SPACE (NBSP)`
+///
+/// Names come from the Unicode character database. Control characters,
+/// which have no name, use their formal alias (`U+0009 CHARACTER
+/// TABULATION`); unassigned code points show `<unassigned>` and
+/// undecodable bytes `\xHH <invalid UTF-8>`. Common invisible characters
+/// get their conventional abbreviation appended.
+pub(crate) fn format_char_label(code: u32) -> String {
+    if let Some(byte) = invalid_byte(code) {
+        return format!("\\x{byte:02x} <invalid UTF-8>");
+    }
+    let name = char::from_u32(code)
+        .and_then(unicode_names2::name)
+        .map(|name| name.to_string())
+        .or_else(|| control_alias(code).map(str::to_string))
+        .unwrap_or_else(|| match code {
+            0..=0x1F | 0x7F..=0x9F => "<control>".to_string(),
+            _ => "<unassigned>".to_string(),
+        });
+    match char_abbreviation(code) {
+        Some(abbreviation) => format!("U+{code:04X} {name} ({abbreviation})"),
+        None => format!("U+{code:04X} {name}"),
+    }
+}
+
+/// Formal name aliases for the control characters that show up in source files
+fn control_alias(code: u32) -> Option<&'static str> {
+    Some(match code {
+        0x00 => "NULL",
+        0x07 => "BELL",
+        0x08 => "BACKSPACE",
+        0x09 => "CHARACTER TABULATION",
+        0x0A => "LINE FEED",
+        0x0B => "LINE TABULATION",
+        0x0C => "FORM FEED",
+        0x0D => "CARRIAGE RETURN",
+        0x1B => "ESCAPE",
+        0x7F => "DELETE",
+        0x85 => "NEXT LINE",
+        _ => return None,
+    })
+}
+
+/// Conventional abbreviation for whitespace, line-ending and invisible codes
+fn char_abbreviation(code: u32) -> Option<&'static str> {
+    Some(match code {
         9 => "TAB",
         10 => "LF",
+        11 => "VT",
+        12 => "FF",
         13 => "CR",
-        32 => "SPACE",
+        0x85 => "NEL",
         160 => "NBSP",
+        0xAD => "SHY",
+        0x034F => "CGJ",
+        0x061C => "ALM",
+        0x180E => "MVS",
         0x200B => "ZWSP",
         0x200C => "ZWNJ",
         0x200D => "ZWJ",
+        0x200E => "LRM",
+        0x200F => "RLM",
+        0x2028 => "LS",
+        0x2029 => "PS",
+        0x202A => "LRE",
+        0x202B => "RLE",
+        0x202C => "PDF",
+        0x202D => "LRO",
+        0x202E => "RLO",
+        0x202F => "NNBSP",
+        0x205F => "MMSP",
+        0x2060 => "WJ",
+        0x2066 => "LRI",
+        0x2067 => "RLI",
+        0x2068 => "FSI",
+        0x2069 => "PDI",
+        0xFE00..=0xFE0F | 0xE0100..=0xE01EF => "VS",
         0xFEFF => "BOM",
-        _ => "?",
-    }
+        _ => return None,
+    })
+}
+
+/// Whether `code` is a `Default_Ignorable_Code_Point`
+pub(crate) fn is_default_ignorable(code: u32) -> bool {
+    DEFAULT_IGNORABLE_RANGES
+        .iter()
+        .any(|&(start, end)| (start..=end).contains(&code))
+}
+
+/// Whether `code` is Unicode `White_Space`
+fn is_unicode_whitespace(code: u32) -> bool {
+    char::from_u32(code).is_some_and(char::is_whitespace)
+}
+
+/// Whether `code` is a space other than ASCII whitespace and line separators
+/// (NBSP, EM SPACE, IDEOGRAPHIC SPACE, ...)
+pub(crate) fn is_exotic_space(code: u32) -> bool {
+    is_unicode_whitespace(code) && !matches!(code, 9..=13 | 32 | 0x85 | 0x2028 | 0x2029)
 }
 
 /// Classify characters by type for semantic grouping
@@ -307,10 +743,14 @@ fn classify_characters(codes: &HashMap<u32, usize>) -> CharCodeClassification {
 
     for (code, count) in codes {
         match *code {
-            9 | 32 | 160 => classification.whitespace.push((*code, *count)),
-            10 | 13 => classification.line_endings.push((*code, *count)),
+            10 | 13 | 0x85 | 0x2028 | 0x2029 => {
+                classification.line_endings.push((*code, *count));
+            }
+            code if is_unicode_whitespace(code) => classification.whitespace.push((code, *count)),
+            code if is_default_ignorable(code) => classification.invisible.push((code, *count)),
             0..=31 | 127 => classification.control.push((*code, *count)),
             33..=126 => classification.printable.push((*code, *count)),
+            INVALID_BYTE_BASE.. => classification.invalid.push((*code, *count)),
             _ => classification.unicode.push((*code, *count)),
         }
     }
@@ -321,8 +761,7 @@ fn classify_characters(codes: &HashMap<u32, usize>) -> CharCodeClassification {
 /// Detect common whitespace/formatting issues
 fn detect_whitespace_issues(
     codes: &HashMap<u32, usize>,
-    expected_diff: &str,
-    actual_diff: &str,
+    trailing_whitespace: usize,
 ) -> Vec<WhitespaceIssue> {
     let mut issues = Vec::new();
 
@@ -343,14 +782,11 @@ fn detect_whitespace_issues(
         issues.push(WhitespaceIssue::ExtraSpaces);
     }
 
-    // Check for trailing whitespace
-    if expected_diff
-        .lines()
-        .any(|line| line.ends_with(' ') || line.ends_with('\t'))
-        || actual_diff
-            .lines()
-            .any(|line| line.ends_with(' ') || line.ends_with('\t'))
-    {
+    if codes.keys().any(|&code| is_exotic_space(code)) {
+        issues.push(WhitespaceIssue::ExoticSpaces);
+    }
+
+    if trailing_whitespace > 0 {
         issues.push(WhitespaceIssue::TrailingWhitespace);
     }
 
@@ -369,6 +805,10 @@ fn detect_encoding_issues(codes: &HashMap<u32, usize>) -> Vec<EncodingIssue> {
         issues.push(EncodingIssue::ByteOrderMark);
     }
 
+    if codes.keys().any(|&code| invalid_byte(code).is_some()) {
+        issues.push(EncodingIssue::InvalidUtf8);
+    }
+
     for code in codes.keys() {
         if (0xD800..=0xDFFF).contains(code) {
             issues.push(EncodingIssue::Utf16Surrogate);
@@ -379,10 +819,88 @@ fn detect_encoding_issues(codes: &HashMap<u32, usize>) -> Vec<EncodingIssue> {
     issues
 }
 
+/// Detect bidirectional control characters in either text
+fn detect_security_issues(expected: &[u32], actual: &[u32]) -> Vec<SecurityIssue> {
+    let mut issues = Vec::new();
+
+    for codes in [expected, actual] {
+        for &code in codes {
+            let Some(issue) = bidi_issue(code) else {
+                continue;
+            };
+            if !issues.contains(&issue) {
+                issues.push(issue);
+            }
+        }
+
+        if !bidi_balanced(codes) && !issues.contains(&SecurityIssue::UnbalancedBidi) {
+            issues.push(SecurityIssue::UnbalancedBidi);
+        }
+    }
+
+    issues
+}
+
+/// Whether every embedding/override and isolate is closed on its own line
+///
+/// Paragraph separators terminate all bidi state (UAX #9), so an unclosed
+/// control would leak its reordering to the rest of the line.
+fn bidi_balanced(codes: &[u32]) -> bool {
+    let mut embeddings = 0usize;
+    let mut isolates = 0usize;
+
+    for &code in codes {
+        match code {
+            0x202A | 0x202B | 0x202D | 0x202E => embeddings += 1,
+            0x2066..=0x2068 => isolates += 1,
+            0x202C => match embeddings.checked_sub(1) {
+                Some(depth) => embeddings = depth,
+                None => return false,
+            },
+            0x2069 => match isolates.checked_sub(1) {
+                // PDI also closes embeddings opened inside the isolate
+                Some(depth) => {
+                    isolates = depth;
+                    embeddings = 0;
+                }
+                None => return false,
+            },
+            10 | 13 | 0x2029 if embeddings > 0 || isolates > 0 => return false,
+            _ => {}
+        }
+    }
+
+    embeddings == 0 && isolates == 0
+}
+
+/// Typographic characters in the diffs, if ASCII-fying them makes the diffs equal
+fn detect_smart_punctuation(expected_diff: &str, actual_diff: &str) -> Vec<char> {
+    if expected_diff == actual_diff || asciify(expected_diff).text != asciify(actual_diff).text {
+        return Vec::new();
+    }
+
+    let mut chars: Vec<char> = Vec::new();
+    for c in expected_diff.chars().chain(actual_diff.chars()) {
+        if ascii_equivalent(c).is_some() && !chars.contains(&c) {
+            chars.push(c);
+        }
+    }
+    chars
+}
+
+/// Format smart punctuation as `“→", –→-`
+fn format_smart_punctuation(chars: &[char]) -> String {
+    chars
+        .iter()
+        .map(|&c| format!("{c}→{}", ascii_equivalent(c).unwrap_or_default()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Compare character distributions between expected and actual
-fn compare_distribution(expected: &str, actual: &str) -> CharDistribution {
-    let exp_codes = count_chars(expected);
-    let act_codes = count_chars(actual);
+fn compare_distribution(expected: &[u32], actual: &[u32]) -> CharDistribution {
+    let exp_codes = count_codes(expected);
+    let act_codes = count_codes(actual);
 
     let mut dist = CharDistribution::default();
 
@@ -404,97 +922,472 @@ fn compare_distribution(expected: &str, actual: &str) -> CharDistribution {
 }
 
 /// Count character occurrences
-fn count_chars(s: &str) -> HashMap<u32, usize> {
-    let mut codes = HashMap::new();
-    for ch in s.chars() {
-        *codes.entry(ch as u32).or_insert(0) += 1;
+fn count_codes(codes: &[u32]) -> HashMap<u32, usize> {
+    let mut counts = HashMap::new();
+    for &code in codes {
+        *counts.entry(code).or_insert(0) += 1;
     }
-    codes
+    counts
 }
 
 /// Analyze Unicode normalization
-fn analyze_unicode(expected: &str, actual: &str) -> UnicodeAnalysis {
+///
+/// Canonical (NFC) equivalence is checked first; compatibility (NFKC)
+/// equivalence is only reported when NFC alone does not reconcile the texts.
+fn analyze_unicode(
+    expected: &str,
+    actual: &str,
+    expected_diff: &str,
+    actual_diff: &str,
+) -> UnicodeAnalysis {
     let exp_nfc: String = expected.nfc().collect();
-    let exp_nfd: String = expected.nfd().collect();
     let act_nfc: String = actual.nfc().collect();
-    let act_nfd: String = actual.nfd().collect();
+    let normalization_mismatch = exp_nfc == act_nfc && expected != actual;
+
+    let compatibility_mismatch =
+        !normalization_mismatch && expected != actual && expected.nfkc().eq(actual.nfkc());
+
+    let mut compatibility_mappings: Vec<CompatibilityMapping> = Vec::new();
+    if compatibility_mismatch {
+        for c in expected_diff.chars().chain(actual_diff.chars()) {
+            let to: String = std::iter::once(c).nfkc().collect();
+            let canonical: String = std::iter::once(c).nfc().collect();
+            if to != canonical && !compatibility_mappings.iter().any(|m| m.from == c) {
+                compatibility_mappings.push(CompatibilityMapping { from: c, to });
+            }
+        }
+    }
 
     UnicodeAnalysis {
-        has_composed: exp_nfc == expected || act_nfc == actual,
-        has_decomposed: exp_nfd != expected || act_nfd != actual,
-        normalization_mismatch: exp_nfc == act_nfc && expected != actual,
+        // Precomposed characters change under NFD; combining sequences under NFC
+        has_composed: !is_nfd(expected) || !is_nfd(actual),
+        has_decomposed: !is_nfc(expected) || !is_nfc(actual),
+        normalization_mismatch,
+        compatibility_mismatch,
+        compatibility_mappings,
     }
 }
 
-/// Generate smart fix suggestion based on detected issues
-fn generate_suggestion(
-    whitespace_issues: &[WhitespaceIssue],
-    encoding_issues: &[EncodingIssue],
+/// Join terminal artefact labels, e.g. `SGR colour/style code, backspace`
+fn format_terminal_artefacts(artefacts: &[TerminalArtefact]) -> String {
+    artefacts
+        .iter()
+        .map(TerminalArtefact::label)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Render compatibility mappings compactly, e.g. `ﬁ→fi, ａ→a`
+fn format_compatibility_mappings(mappings: &[CompatibilityMapping]) -> String {
+    mappings
+        .iter()
+        .map(|m| format!("{}→{}", m.from, m.to))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Issues detected by the individual analysis steps
+struct DetectedIssues<'a> {
+    security: &'a [SecurityIssue],
+    whitespace: &'a [WhitespaceIssue],
+    encoding: &'a [EncodingIssue],
     has_zero_width: bool,
-    unicode_analysis: &UnicodeAnalysis,
-) -> Option<String> {
-    if has_zero_width {
-        return Some("Remove zero-width characters from your search string".to_string());
+    confusables: &'a [Confusable],
+    smart_punctuation: &'a [char],
+    escaping: Option<&'a EscapeMismatch>,
+    tool_artefacts: &'a [ToolArtefact],
+    terminal_artefacts: &'a [TerminalArtefact],
+    expected_text: &'a str,
+    actual_text: &'a str,
+    unicode: &'a UnicodeAnalysis,
+}
+
+/// Score every detected issue and sort by severity, share explained, confidence
+///
+/// A finding's share is the number of diff characters it accounts for.
+/// Verified findings (smart punctuation, normalization) explain the whole
+/// diff by construction and carry full confidence.
+fn rank_findings(
+    issues: &DetectedIssues<'_>,
+    codes: &HashMap<u32, usize>,
+    diff_length: usize,
+    trailing_whitespace: usize,
+) -> Vec<Finding> {
+    let count = |matches: &dyn Fn(u32) -> bool| -> usize {
+        codes
+            .iter()
+            .filter(|&(&code, _)| matches(code))
+            .map(|(_, &n)| n)
+            .sum()
+    };
+
+    let mut findings = Vec::new();
+    let mut add = |kind: FindingKind, severity, confidence, explained_chars: usize| {
+        let suggestion = finding_suggestion(&kind, issues);
+        let explained_chars = explained_chars.min(diff_length);
+        let explained = if diff_length == 0 {
+            0.0
+        } else {
+            explained_chars as f64 / diff_length as f64
+        };
+        findings.push(Finding {
+            kind,
+            severity,
+            confidence,
+            explained_chars,
+            explained,
+            suggestion,
+        });
+    };
+
+    for issue in issues.security {
+        let explained = count(&|code| match issue {
+            SecurityIssue::UnbalancedBidi => BIDI_CONTROL_CHARS.contains(&code),
+            _ => bidi_issue(code).as_ref() == Some(issue),
+        });
+        // Marks are invisible like zero-width characters: a likely cause when
+        // they are in the diff, a note otherwise
+        let (severity, confidence) = if issue.is_critical() {
+            (Severity::Critical, 1.0)
+        } else if explained > 0 {
+            (Severity::Warning, 0.95)
+        } else {
+            (Severity::Info, 0.5)
+        };
+        add(
+            FindingKind::Security(issue.clone()),
+            severity,
+            confidence,
+            explained,
+        );
+    }
+
+    if issues.has_zero_width {
+        let explained =
+            count(&|code| is_default_ignorable(code) && !BIDI_CONTROL_CHARS.contains(&code));
+        add(FindingKind::ZeroWidth, Severity::Warning, 0.95, explained);
+    }
+
+    if !issues.smart_punctuation.is_empty() {
+        add(
+            FindingKind::SmartPunctuation,
+            Severity::Warning,
+            1.0,
+            diff_length,
+        );
+    }
+
+    if issues.escaping.is_some() {
+        // Verified: the transformed search string equals the actual text
+        add(FindingKind::Escaping, Severity::Warning, 1.0, diff_length);
+    }
+
+    for &artefact in issues.tool_artefacts {
+        let stripped = strip_artefact(issues.expected_text, artefact);
+        // Verified when stripping alone makes the texts equal
+        let (confidence, explained) = if stripped == issues.actual_text {
+            (1.0, diff_length)
+        } else {
+            (
+                0.85,
+                issues.expected_text.chars().count() - stripped.chars().count(),
+            )
+        };
+        add(
+            FindingKind::ToolArtefact(artefact),
+            Severity::Warning,
+            confidence,
+            explained,
+        );
+    }
+
+    if !issues.terminal_artefacts.is_empty() {
+        let rendered = render_terminal(issues.expected_text);
+        // Verified when rendering alone makes the texts equal
+        let (confidence, explained) = if rendered == issues.actual_text {
+            (1.0, diff_length)
+        } else {
+            (
+                0.9,
+                issues.expected_text.chars().count() - rendered.chars().count(),
+            )
+        };
+        add(
+            FindingKind::TerminalArtefacts,
+            Severity::Warning,
+            confidence,
+            explained,
+        );
     }
 
-    if unicode_analysis.normalization_mismatch {
-        return Some("Normalize Unicode to NFC form in your search string".to_string());
+    if !issues.confusables.is_empty() {
+        let chars: Vec<u32> = issues
+            .confusables
+            .iter()
+            .flat_map(|pair| pair.expected.chars().chain(pair.actual.chars()))
+            .map(u32::from)
+            .collect();
+        let explained = count(&|code| chars.contains(&code));
+        add(FindingKind::Confusables, Severity::Warning, 0.9, explained);
+    }
+
+    if issues.unicode.normalization_mismatch {
+        add(
+            FindingKind::NormalizationMismatch,
+            Severity::Warning,
+            1.0,
+            diff_length,
+        );
+    }
+
+    // Spaces and punctuation that NFKC folds have their own, more specific findings
+    let compatibility_only = issues.unicode.compatibility_mappings.iter().any(|mapping| {
+        !is_exotic_space(u32::from(mapping.from)) && ascii_equivalent(mapping.from).is_none()
+    });
+    if issues.unicode.compatibility_mismatch && compatibility_only {
+        // Verified: NFKC forms are equal
+        add(
+            FindingKind::CompatibilityNormalization,
+            Severity::Warning,
+            1.0,
+            diff_length,
+        );
+    }
+
+    for issue in issues.whitespace {
+        let (severity, confidence, explained) = match issue {
+            WhitespaceIssue::TabsVsSpaces => (
+                Severity::Warning,
+                0.8,
+                count(&|code| code == 9 || code == 32),
+            ),
+            WhitespaceIssue::MixedLineEndings => (
+                Severity::Warning,
+                0.8,
+                count(&|code| code == 10 || code == 13),
+            ),
+            WhitespaceIssue::ExtraSpaces => (Severity::Info, 0.5, count(&|code| code == 32)),
+            WhitespaceIssue::TrailingWhitespace => (Severity::Info, 0.6, trailing_whitespace),
+            WhitespaceIssue::ExoticSpaces => {
+                let plain = |text: &str| -> String {
+                    text.chars()
+                        .map(|c| {
+                            if is_exotic_space(u32::from(c)) {
+                                ' '
+                            } else {
+                                c
+                            }
+                        })
+                        .collect()
+                };
+                // Verified when replacing the spaces alone makes the texts equal
+                if plain(issues.expected_text) == plain(issues.actual_text) {
+                    (Severity::Warning, 1.0, diff_length)
+                } else {
+                    (Severity::Warning, 0.9, count(&|code| is_exotic_space(code)))
+                }
+            }
+        };
+        add(
+            FindingKind::Whitespace(issue.clone()),
+            severity,
+            confidence,
+            explained,
+        );
+    }
+
+    for issue in issues.encoding {
+        let (confidence, explained) = match issue {
+            EncodingIssue::ReplacementChar => (0.7, count(&|code| code == 0xFFFD)),
+            EncodingIssue::ByteOrderMark => (0.9, count(&|code| code == 0xFEFF)),
+            EncodingIssue::Utf16Surrogate => {
+                (0.9, count(&|code| (0xD800..=0xDFFF).contains(&code)))
+            }
+            EncodingIssue::InvalidUtf8 => (0.95, count(&|code| invalid_byte(code).is_some())),
+        };
+        add(
+            FindingKind::Encoding(issue.clone()),
+            Severity::Error,
+            confidence,
+            explained,
+        );
     }
 
-    if let Some(issue) = whitespace_issues.first() {
-        match issue {
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then(b.explained.total_cmp(&a.explained))
+            .then(b.confidence.total_cmp(&a.confidence))
+    });
+    findings
+}
+
+/// Security issue raised by a bidi control character
+fn bidi_issue(code: u32) -> Option<SecurityIssue> {
+    match code {
+        0x202D | 0x202E => Some(SecurityIssue::BidiOverride),
+        0x202A | 0x202B => Some(SecurityIssue::BidiEmbedding),
+        0x2066..=0x2068 => Some(SecurityIssue::BidiIsolate),
+        0x200E | 0x200F | 0x061C => Some(SecurityIssue::BidiMark),
+        _ => None,
+    }
+}
+
+/// Count spaces/tabs at the ends of lines in a diff fragment
+///
+/// The fragment's last line only counts when a line ends right after it.
+fn trailing_whitespace_len(text: &str, at_line_end: bool) -> usize {
+    text.split_inclusive('\n')
+        .filter(|line| at_line_end || line.ends_with('\n'))
+        .map(|line| {
+            let content = line.trim_end_matches(['\n', '\r']);
+            content.len() - content.trim_end_matches([' ', '\t']).len()
+        })
+        .sum()
+}
+
+/// Actionable fix for one finding
+fn finding_suggestion(kind: &FindingKind, issues: &DetectedIssues<'_>) -> String {
+    match kind {
+        FindingKind::Security(SecurityIssue::BidiMark) => {
+            "Text contains invisible directional marks (LRM/RLM/ALM); they are normal in \
+             right-to-left text, so copy them exactly from the file or remove them from your \
+             search string"
+                .to_string()
+        }
+        FindingKind::Security(_) => {
+            "SECURITY: Text contains bidirectional control characters that make code display \
+             differently from how it compiles (Trojan Source); review the code and remove them \
+             with strip_bidi_controls"
+                .to_string()
+        }
+        FindingKind::ZeroWidth => {
+            "Remove zero-width characters from your search string".to_string()
+        }
+        FindingKind::SmartPunctuation => format!(
+            "Only typographic punctuation differs ({}); copy the exact quotes/dashes from the file",
+            format_smart_punctuation(issues.smart_punctuation)
+        ),
+        FindingKind::Escaping => match issues.escaping {
+            Some(mismatch) => format!("Escaping differs: {}", mismatch.description()),
+            None => "Escaping differs between search string and file".to_string(),
+        },
+        FindingKind::ToolArtefact(artefact) => format!(
+            "Search string contains a {} copied from tool output; remove it (strip_artefacts)",
+            artefact.label()
+        ),
+        FindingKind::TerminalArtefacts => format!(
+            "Search string contains terminal output artefacts ({}); remove them (render_terminal)",
+            format_terminal_artefacts(issues.terminal_artefacts)
+        ),
+        FindingKind::Confusables => {
+            let pairs: Vec<String> = issues
+                .confusables
+                .iter()
+                .map(Confusable::describe)
+                .collect();
+            format!(
+                "Search string uses look-alike characters ({}); copy the text from the file",
+                pairs.join(", ")
+            )
+        }
+        FindingKind::NormalizationMismatch => {
+            "Normalize Unicode to NFC form in your search string".to_string()
+        }
+        FindingKind::CompatibilityNormalization => format!(
+            "Only compatibility characters differ ({}); normalize to NFKC or copy the text from the file",
+            format_compatibility_mappings(&issues.unicode.compatibility_mappings)
+        ),
+        FindingKind::Whitespace(issue) => match issue {
             WhitespaceIssue::TabsVsSpaces => {
-                return Some(
-                    "Replace tabs with spaces (or vice versa) in your search string".to_string(),
-                );
+                "Replace tabs with spaces (or vice versa) in your search string".to_string()
             }
             WhitespaceIssue::MixedLineEndings => {
-                return Some("Use consistent line endings (LF or CRLF, not mixed)".to_string());
+                "Use consistent line endings (LF or CRLF, not mixed)".to_string()
             }
             WhitespaceIssue::ExtraSpaces => {
-                return Some("Check for extra/missing spaces in your search string".to_string());
+                "Check for extra/missing spaces in your search string".to_string()
             }
             WhitespaceIssue::TrailingWhitespace => {
-                return Some(
-                    "Remove trailing whitespace from lines in your search string".to_string(),
-                );
+                "Remove trailing whitespace from lines in your search string".to_string()
             }
-        }
-    }
-
-    if let Some(issue) = encoding_issues.first() {
-        match issue {
+            WhitespaceIssue::ExoticSpaces => {
+                "Replace non-ASCII spaces (NBSP, EM SPACE, ...) with regular spaces".to_string()
+            }
+        },
+        FindingKind::Encoding(issue) => match issue {
             EncodingIssue::ReplacementChar => {
-                return Some("File contains invalid UTF-8 characters (�)".to_string());
+                "File contains invalid UTF-8 characters (�)".to_string()
             }
             EncodingIssue::ByteOrderMark => {
-                return Some("Remove Byte Order Mark (BOM) from file".to_string());
+                "Byte Order Mark (BOM) on one side only - strip it before matching and restore it on write".to_string()
             }
             EncodingIssue::Utf16Surrogate => {
-                return Some("File contains invalid UTF-16 surrogate characters".to_string());
+                "File contains invalid UTF-16 surrogate characters".to_string()
             }
-        }
+            EncodingIssue::InvalidUtf8 => {
+                "File is not valid UTF-8; match the raw bytes or transcode the file first"
+                    .to_string()
+            }
+        },
     }
+}
+
+/// Generate smart fix suggestion from ranked findings
+///
+/// The top finding's fix comes first; when several findings account for
+/// parts of the diff, a breakdown of what each explains follows.
+fn generate_suggestion(findings: &[Finding], diff_length: usize) -> Option<String> {
+    let top = findings.first()?;
 
-    None
+    let contributing: Vec<&Finding> = findings.iter().filter(|f| f.explained_chars > 0).collect();
+    if contributing.len() < 2 {
+        return Some(top.suggestion.clone());
+    }
+
+    let breakdown: Vec<String> = contributing
+        .iter()
+        .map(|f| {
+            format!(
+                "{} explains {} of the diff",
+                f.label(),
+                format_share(f.explained_chars, diff_length)
+            )
+        })
+        .collect();
+    Some(format!("{} ({})", top.suggestion, breakdown.join(", ")))
+}
+
+/// Format `part` of `total` as a small fraction ("1/3") or a percentage
+fn format_share(part: usize, total: usize) -> String {
+    if total == 0 || part >= total {
+        return "all".to_string();
+    }
+    let divisor = gcd(part, total);
+    let (numerator, denominator) = (part / divisor, total / divisor);
+    if denominator <= 12 {
+        format!("{numerator}/{denominator}")
+    } else {
+        format!("{}%", part * 100 / total)
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
 }
 
 /// Format visual diff with inline character codes
 fn format_visual_diff_with_codes(
-    expected: &str,
-    _actual: &str,
-    expected_diff: &str,
-    actual_diff: &str,
+    exp_codes: &[u32],
+    exp_diff_codes: &[u32],
+    act_diff_codes: &[u32],
     prefix_len: usize,
     suffix_len: usize,
 ) -> String {
-    let exp_chars: Vec<char> = expected.chars().collect();
-
-    let prefix: String = exp_chars.iter().take(prefix_len).collect();
-    let suffix: String = exp_chars
-        .iter()
-        .skip(exp_chars.len().saturating_sub(suffix_len))
-        .collect();
+    let prefix = render_codes(&exp_codes[..prefix_len]);
+    let suffix = render_codes(&exp_codes[exp_codes.len().saturating_sub(suffix_len)..]);
+    let expected_diff = render_codes(exp_diff_codes);
+    let actual_diff = render_codes(act_diff_codes);
 
     // Format as: prefix{-removed-}{+added+}suffix
     let mut output = String::new();
@@ -512,24 +1405,28 @@ fn format_visual_diff_with_codes(
     output.push_str(&format!(
         "\nExpected diff: {:?} [{}]",
         expected_diff,
-        inline_codes(expected_diff)
+        inline_codes(exp_diff_codes)
     ));
     output.push_str(&format!(
         "\nActual diff:   {:?} [{}]",
         actual_diff,
-        inline_codes(actual_diff)
+        inline_codes(act_diff_codes)
     ));
 
     output
 }
 
-/// Format string as comma-separated character codes
-fn inline_codes(s: &str) -> String {
-    if s.is_empty() {
+/// Format codes as comma-separated character codes
+fn inline_codes(codes: &[u32]) -> String {
+    if codes.is_empty() {
         return String::from("empty");
     }
-    s.chars()
-        .map(|c| format!("{}", c as u32))
+    codes
+        .iter()
+        .map(|&code| match invalid_byte(code) {
+            Some(byte) => format!("\\x{byte:02x}"),
+            None => code.to_string(),
+        })
         .collect::<Vec<_>>()
         .join(",")
 }
@@ -556,6 +1453,8 @@ impl CharCodeData {
         // Classification
         if !self.classification.whitespace.is_empty()
             || !self.classification.line_endings.is_empty()
+            || !self.classification.invisible.is_empty()
+            || !self.classification.invalid.is_empty()
         {
             output.push_str("\nCharacter Types:\n");
 
@@ -564,7 +1463,7 @@ impl CharCodeData {
                     .classification
                     .whitespace
                     .iter()
-                    .map(|(code, count)| format!("{}×{}", format_char_name(*code), count))
+                    .map(|(code, count)| format!("{}×{}", format_char_label(*code), count))
                     .collect();
                 output.push_str(&format!("  Whitespace: {}\n", ws.join(", ")));
             }
@@ -574,11 +1473,21 @@ impl CharCodeData {
                     .classification
                     .line_endings
                     .iter()
-                    .map(|(code, count)| format!("{}×{}", format_char_name(*code), count))
+                    .map(|(code, count)| format!("{}×{}", format_char_label(*code), count))
                     .collect();
                 output.push_str(&format!("  Line endings: {}\n", le.join(", ")));
             }
 
+            if !self.classification.invisible.is_empty() {
+                let inv: Vec<String> = self
+                    .classification
+                    .invisible
+                    .iter()
+                    .map(|(code, count)| format!("{}×{}", format_char_label(*code), count))
+                    .collect();
+                output.push_str(&format!("  Invisible: {}\n", inv.join(", ")));
+            }
+
             if !self.classification.control.is_empty() {
                 output.push_str(&format!(
                     "  Control chars: {} types\n",
@@ -592,15 +1501,41 @@ impl CharCodeData {
                     self.classification.unicode.len()
                 ));
             }
+
+            if !self.classification.invalid.is_empty() {
+                let bytes: Vec<String> = self
+                    .classification
+                    .invalid
+                    .iter()
+                    .map(|(code, count)| format!("{}×{}", format_char_label(*code), count))
+                    .collect();
+                output.push_str(&format!("  Invalid UTF-8 bytes: {}\n", bytes.join(", ")));
+            }
         }
 
         // Issues detected
         if !self.whitespace_issues.is_empty()
             || !self.encoding_issues.is_empty()
+            || !self.security_issues.is_empty()
             || self.has_zero_width
+            || !self.confusables.is_empty()
+            || !self.smart_punctuation.is_empty()
+            || self.escaping.is_some()
+            || !self.tool_artefacts.is_empty()
+            || !self.terminal_artefacts.is_empty()
+            || self.unicode_analysis.normalization_mismatch
+            || self.unicode_analysis.compatibility_mismatch
         {
             output.push_str("\nIssues Detected:\n");
 
+            for issue in &self.security_issues {
+                if issue.is_critical() {
+                    output.push_str(&format!("  🚨 Security: {issue:?}\n"));
+                } else {
+                    output.push_str(&format!("  ⚠️  {issue:?}\n"));
+                }
+            }
+
             for issue in &self.whitespace_issues {
                 output.push_str(&format!("  ⚠️  {issue:?}\n"));
             }
@@ -610,12 +1545,63 @@ impl CharCodeData {
             }
 
             if self.has_zero_width {
-                output.push_str("  ⚠️  Zero-width characters detected\n");
+                output.push_str("  ⚠️  Invisible (zero-width) characters detected\n");
+            }
+
+            for confusable in &self.confusables {
+                output.push_str(&format!("  ⚠️  Confusable: {}\n", confusable.describe()));
+            }
+
+            if !self.smart_punctuation.is_empty() {
+                output.push_str(&format!(
+                    "  ⚠️  Smart punctuation: {}\n",
+                    format_smart_punctuation(&self.smart_punctuation)
+                ));
+            }
+
+            if let Some(mismatch) = &self.escaping {
+                output.push_str(&format!("  ⚠️  Escaping: {}\n", mismatch.description()));
+            }
+
+            for artefact in &self.tool_artefacts {
+                output.push_str(&format!(
+                    "  ⚠️  Tool output artefact: {}\n",
+                    artefact.label()
+                ));
+            }
+
+            if !self.terminal_artefacts.is_empty() {
+                output.push_str(&format!(
+                    "  ⚠️  Terminal artefacts: {}\n",
+                    format_terminal_artefacts(&self.terminal_artefacts)
+                ));
             }
 
             if self.unicode_analysis.normalization_mismatch {
                 output.push_str("  ⚠️  Unicode normalization mismatch (NFC vs NFD)\n");
             }
+
+            if self.unicode_analysis.compatibility_mismatch {
+                output.push_str(&format!(
+                    "  ⚠️  Compatibility characters (NFKC): {}\n",
+                    format_compatibility_mappings(&self.unicode_analysis.compatibility_mappings)
+                ));
+            }
+        }
+
+        // Ranked findings
+        if !self.findings.is_empty() {
+            output.push_str("\nFindings (most important first):\n");
+            for (i, finding) in self.findings.iter().enumerate() {
+                output.push_str(&format!(
+                    "  {}. [{:?}] {}: explains {} of the diff, confidence {:.0}%\n",
+                    i + 1,
+                    finding.severity,
+                    finding.label(),
+                    format_share(finding.explained_chars, self.diff_length),
+                    finding.confidence * 100.0
+                ));
+            }
         }
 
         // Distribution
@@ -629,7 +1615,7 @@ impl CharCodeData {
                     .distribution
                     .only_in_expected
                     .iter()
-                    .map(|(code, count)| format!("{}×{}", format_char_name(*code), count))
+                    .map(|(code, count)| format!("{}×{}", format_char_label(*code), count))
                     .collect();
                 output.push_str(&format!("  Only in search string: {}\n", chars.join(", ")));
             }
@@ -639,7 +1625,7 @@ impl CharCodeData {
                     .distribution
                     .only_in_actual
                     .iter()
-                    .map(|(code, count)| format!("{}×{}", format_char_name(*code), count))
+                    .map(|(code, count)| format!("{}×{}", format_char_label(*code), count))
                     .collect();
                 output.push_str(&format!("  Only in found string: {}\n", chars.join(", ")));
             }
@@ -658,3 +1644,715 @@ impl CharCodeData {
         output
     }
 }
+
+// ============================================================================
+// PER-LINE COMPARISON
+// ============================================================================
+
+/// How a line of the search block compares to the found block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LineStatus {
+    Identical,
+    LineEnding,         // Only the terminator differs
+    TrailingWhitespace, // Only whitespace at the end differs
+    TabsVsSpaces,       // Indentation uses tabs on one side, spaces on the other
+    WhitespaceOnly,     // Same words, different spacing
+    Content,            // Anything else
+    Missing,            // Search line has no counterpart in the found block
+    Extra,              // Found line has no counterpart in the search block
+}
+
+impl LineStatus {
+    /// Short human-readable description
+    #[must_use]
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Identical => "identical",
+            Self::LineEnding => "line ending",
+            Self::TrailingWhitespace => "trailing whitespace",
+            Self::TabsVsSpaces => "tabs vs spaces",
+            Self::WhitespaceOnly => "whitespace only",
+            Self::Content => "content",
+            Self::Missing => "missing from file",
+            Self::Extra => "extra in file",
+        }
+    }
+}
+
+/// One line of the search block paired with its counterpart in the file
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LineComparison {
+    /// 1-based line number in the search block
+    pub expected_line: Option<usize>,
+
+    /// 1-based line number in the found block
+    pub actual_line: Option<usize>,
+
+    /// Search line, without terminator
+    pub expected: Option<String>,
+
+    /// Found line, without terminator
+    pub actual: Option<String>,
+
+    pub status: LineStatus,
+
+    /// Character analysis of a paired line that differs
+    pub analysis: Option<CharCodeData>,
+}
+
+/// Line-by-line comparison of a multi-line search block with the found block
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LineByLineAnalysis {
+    /// Every line, in order
+    pub lines: Vec<LineComparison>,
+}
+
+impl LineByLineAnalysis {
+    /// Whether every line is identical
+    #[must_use]
+    pub fn is_identical(&self) -> bool {
+        self.failing().next().is_none()
+    }
+
+    /// Lines that differ, in order
+    pub fn failing(&self) -> impl Iterator<Item = &LineComparison> {
+        self.lines
+            .iter()
+            .filter(|line| line.status != LineStatus::Identical)
+    }
+
+    /// One-line summary, e.g. "2 of 5 lines differ: 1 trailing whitespace, 1 content"
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let failing: Vec<&LineComparison> = self.failing().collect();
+        if failing.is_empty() {
+            return format!("All {} lines identical", self.lines.len());
+        }
+
+        // Statuses in order of first appearance
+        let mut statuses: Vec<LineStatus> = Vec::new();
+        for line in &failing {
+            if !statuses.contains(&line.status) {
+                statuses.push(line.status);
+            }
+        }
+        let counts: Vec<String> = statuses
+            .iter()
+            .map(|&status| {
+                let count = failing.iter().filter(|line| line.status == status).count();
+                format!("{count} {}", status.description())
+            })
+            .collect();
+
+        format!(
+            "{} of {} lines differ: {}",
+            failing.len(),
+            self.lines.len(),
+            counts.join(", ")
+        )
+    }
+
+    /// Summary followed by one row per differing line, with its fix
+    #[must_use]
+    pub fn format(&self) -> String {
+        let mut output = self.summary();
+        output.push('\n');
+        for line in self.failing() {
+            let position = match (line.expected_line, line.actual_line) {
+                (Some(e), Some(a)) if e == a => format!("line {e}"),
+                (Some(e), Some(a)) => format!("line {e} (file line {a})"),
+                (Some(e), None) => format!("line {e}"),
+                (None, Some(a)) => format!("file line {a}"),
+                (None, None) => continue,
+            };
+            output.push_str(&format!("  {position}: {}\n", line.status.description()));
+            if let Some(expected) = &line.expected {
+                output.push_str(&format!("    - {expected:?}\n"));
+            }
+            if let Some(actual) = &line.actual {
+                output.push_str(&format!("    + {actual:?}\n"));
+            }
+            if let Some(suggestion) = line
+                .analysis
+                .as_ref()
+                .and_then(|analysis| analysis.suggestion.as_ref())
+            {
+                output.push_str(&format!("    → {suggestion}\n"));
+            }
+        }
+        output
+    }
+}
+
+impl CharCodeData {
+    /// Compare a multi-line search block with the found block line by line
+    ///
+    /// Lines are aligned with a line diff; within each changed region,
+    /// search and found lines are paired in order and any surplus is
+    /// reported as missing or extra. Each differing pair gets its own
+    /// [`CharCodeData`] so only the failing lines need fixing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kodegen_utils::char_analysis::{CharCodeData, LineStatus};
+    ///
+    /// let search = "fn a() {\n\tx();\n    y(); \n}\n";
+    /// let found = "fn a() {\n    x();\n    y();\n}\n";
+    /// let lines = CharCodeData::analyze_lines(search, found);
+    ///
+    /// let statuses: Vec<LineStatus> = lines.lines.iter().map(|l| l.status).collect();
+    /// assert_eq!(
+    ///     statuses,
+    ///     [
+    ///         LineStatus::Identical,
+    ///         LineStatus::TabsVsSpaces,
+    ///         LineStatus::TrailingWhitespace,
+    ///         LineStatus::Identical
+    ///     ]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn analyze_lines(expected: &str, actual: &str) -> LineByLineAnalysis {
+        let a = split_lines(expected);
+        let b = split_lines(actual);
+
+        let mut lines = Vec::new();
+        let (mut ai, mut bi) = (0, 0);
+        let mut deleted: Vec<usize> = Vec::new();
+        let mut inserted: Vec<usize> = Vec::new();
+        for (kind, len) in diff_line_slices(&a, &b) {
+            match kind {
+                DiffKind::Delete => {
+                    deleted.extend(ai..ai + len);
+                    ai += len;
+                }
+                DiffKind::Insert => {
+                    inserted.extend(bi..bi + len);
+                    bi += len;
+                }
+                DiffKind::Equal => {
+                    pair_region(&a, &b, &mut deleted, &mut inserted, &mut lines);
+                    for offset in 0..len {
+                        lines.push(compare_line_pair(&a, &b, ai + offset, bi + offset));
+                    }
+                    ai += len;
+                    bi += len;
+                }
+            }
+        }
+        pair_region(&a, &b, &mut deleted, &mut inserted, &mut lines);
+
+        LineByLineAnalysis { lines }
+    }
+}
+
+/// Pair a changed region's lines in order; report the surplus as missing/extra
+fn pair_region(
+    a: &[&str],
+    b: &[&str],
+    deleted: &mut Vec<usize>,
+    inserted: &mut Vec<usize>,
+    lines: &mut Vec<LineComparison>,
+) {
+    let paired = deleted.len().min(inserted.len());
+    for (&ai, &bi) in deleted.iter().zip(inserted.iter()) {
+        lines.push(compare_line_pair(a, b, ai, bi));
+    }
+    for &ai in &deleted[paired..] {
+        lines.push(LineComparison {
+            expected_line: Some(ai + 1),
+            actual_line: None,
+            expected: Some(line_content(a[ai]).to_string()),
+            actual: None,
+            status: LineStatus::Missing,
+            analysis: None,
+        });
+    }
+    for &bi in &inserted[paired..] {
+        lines.push(LineComparison {
+            expected_line: None,
+            actual_line: Some(bi + 1),
+            expected: None,
+            actual: Some(line_content(b[bi]).to_string()),
+            status: LineStatus::Extra,
+            analysis: None,
+        });
+    }
+    deleted.clear();
+    inserted.clear();
+}
+
+fn compare_line_pair(a: &[&str], b: &[&str], ai: usize, bi: usize) -> LineComparison {
+    let expected = line_content(a[ai]);
+    let actual = line_content(b[bi]);
+    let status = classify_line(a[ai], b[bi]);
+    let analysis = match status {
+        LineStatus::Identical | LineStatus::LineEnding => None,
+        // Per-line analyses are never looked up again; keep them out of the
+        // shared cache so they don't evict whole-string results
+        _ => Some(CharCodeData::analyze_with(
+            expected,
+            actual,
+            CachePolicy::Bypass,
+        )),
+    };
+    LineComparison {
+        expected_line: Some(ai + 1),
+        actual_line: Some(bi + 1),
+        expected: Some(expected.to_string()),
+        actual: Some(actual.to_string()),
+        status,
+        analysis,
+    }
+}
+
+/// Classify a pair of lines (with terminators) by the narrowest difference
+fn classify_line(expected: &str, actual: &str) -> LineStatus {
+    if expected == actual {
+        return LineStatus::Identical;
+    }
+    let (expected, actual) = (line_content(expected), line_content(actual));
+    if expected == actual {
+        return LineStatus::LineEnding;
+    }
+    if expected.trim_end() == actual.trim_end() {
+        return LineStatus::TrailingWhitespace;
+    }
+
+    let expected_body = expected.trim_start_matches([' ', '\t']);
+    let actual_body = actual.trim_start_matches([' ', '\t']);
+    if expected_body == actual_body {
+        let expected_indent = &expected[..expected.len() - expected_body.len()];
+        let actual_indent = &actual[..actual.len() - actual_body.len()];
+        if expected_indent.contains('\t') != actual_indent.contains('\t') {
+            return LineStatus::TabsVsSpaces;
+        }
+    }
+
+    if expected.split_whitespace().eq(actual.split_whitespace()) {
+        return LineStatus::WhitespaceOnly;
+    }
+    LineStatus::Content
+}
+
+/// A line without its `\n` / `\r\n` terminator
+fn line_content(line: &str) -> &str {
+    line.strip_suffix('\n')
+        .map_or(line, |line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+// ============================================================================
+// ANALYSIS CACHE
+// ============================================================================
+
+/// Settings for an [`AnalysisCache`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnalysisCacheConfig {
+    /// Maximum number of cached analyses; 0 disables caching
+    pub capacity: usize,
+
+    /// Pairs whose combined input exceeds this many bytes are never cached
+    pub max_entry_bytes: Option<usize>,
+
+    /// Least recently used entries are evicted beyond this many input bytes
+    pub max_total_bytes: Option<usize>,
+}
+
+impl Default for AnalysisCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CACHE_CAPACITY,
+            max_entry_bytes: Some(DEFAULT_CACHE_MAX_ENTRY_BYTES),
+            max_total_bytes: None,
+        }
+    }
+}
+
+/// Counters and current size of an [`AnalysisCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AnalysisCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+
+    /// Entries currently cached
+    pub entries: usize,
+
+    /// Combined input bytes of the cached entries
+    pub bytes: usize,
+}
+
+impl AnalysisCacheStats {
+    /// Share of lookups answered from the cache (0.0 when there were none)
+    #[must_use]
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// How a single analysis call uses the cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Return a cached result if present, otherwise analyze and cache
+    #[default]
+    Use,
+    /// Neither read nor write the cache
+    Bypass,
+    /// Analyze even if cached, and replace the cached result
+    Refresh,
+}
+
+/// Cache key: 128-bit hash of the (expected, actual) pair plus both lengths
+///
+/// `str` hashing is length-delimited, so pairs like `("a|b", "c")` and
+/// `("a", "b|c")` get different keys. Distinct pairs can still collide, so
+/// entries keep their inputs and a hit is only returned when they match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct CacheKey {
+    hashes: [u64; 2],
+    lengths: [usize; 2],
+}
+
+impl CacheKey {
+    fn new(expected: &str, actual: &str) -> Self {
+        let hash = |seed: u8| {
+            let mut hasher = DefaultHasher::new();
+            (seed, expected, actual).hash(&mut hasher);
+            hasher.finish()
+        };
+        Self {
+            hashes: [hash(0), hash(1)],
+            lengths: [expected.len(), actual.len()],
+        }
+    }
+
+    /// Input size the entry stands for
+    fn bytes(&self) -> usize {
+        self.lengths[0] + self.lengths[1]
+    }
+}
+
+/// A cached analysis and the inputs it was computed from
+struct CacheEntry {
+    expected: Box<str>,
+    actual: Box<str>,
+    analysis: CharCodeData,
+}
+
+struct CacheState {
+    config: AnalysisCacheConfig,
+    entries: LruCache<CacheKey, CacheEntry>,
+    stats: AnalysisCacheStats,
+}
+
+impl CacheState {
+    /// Evict least recently used entries until the limits hold
+    fn enforce_limits(&mut self) {
+        let over_bytes = |state: &Self| {
+            state
+                .config
+                .max_total_bytes
+                .is_some_and(|max| state.stats.bytes > max)
+        };
+        while self.entries.len() > self.config.capacity || over_bytes(self) {
+            let Some((key, _)) = self.entries.pop_lru() else {
+                break;
+            };
+            self.stats.bytes -= key.bytes();
+            self.stats.evictions += 1;
+        }
+        self.stats.entries = self.entries.len();
+    }
+}
+
+/// Size-bounded LRU cache of [`CharCodeData`] analyses
+pub struct AnalysisCache {
+    state: Mutex<CacheState>,
+}
+
+impl AnalysisCache {
+    #[must_use]
+    pub fn new(config: AnalysisCacheConfig) -> Self {
+        Self {
+            state: Mutex::new(CacheState {
+                config,
+                entries: LruCache::unbounded(),
+                stats: AnalysisCacheStats::default(),
+            }),
+        }
+    }
+
+    /// Analyze `expected` vs `actual`, consulting the cache according to `policy`
+    pub fn analyze(&self, expected: &str, actual: &str, policy: CachePolicy) -> CharCodeData {
+        let key = CacheKey::new(expected, actual);
+        let cacheable = {
+            let mut state = self.state.lock();
+            let config = state.config;
+            let cacheable = policy != CachePolicy::Bypass
+                && config.capacity > 0
+                && config.max_entry_bytes.is_none_or(|max| key.bytes() <= max);
+
+            if cacheable && policy == CachePolicy::Use {
+                if let Some(entry) = state.entries.get(&key)
+                    && *entry.expected == *expected
+                    && *entry.actual == *actual
+                {
+                    let cached = entry.analysis.clone();
+                    state.stats.hits += 1;
+                    return cached;
+                }
+                state.stats.misses += 1;
+            }
+            cacheable
+        };
+
+        // Analyze without holding the lock
+        let result = CharCodeData::analyze_uncached(expected, actual);
+
+        if cacheable {
+            let mut state = self.state.lock();
+            let entry = CacheEntry {
+                expected: expected.into(),
+                actual: actual.into(),
+                analysis: result.clone(),
+            };
+            // A colliding pair replaces the entry; its key has the same size
+            if state.entries.put(key, entry).is_none() {
+                state.stats.bytes += key.bytes();
+            }
+            state.enforce_limits();
+        }
+        result
+    }
+
+    /// Current settings
+    #[must_use]
+    pub fn config(&self) -> AnalysisCacheConfig {
+        self.state.lock().config
+    }
+
+    /// Change settings, evicting entries that no longer fit
+    pub fn configure(&self, config: AnalysisCacheConfig) {
+        let mut state = self.state.lock();
+        state.config = config;
+        state.enforce_limits();
+    }
+
+    /// Counters and current size
+    #[must_use]
+    pub fn stats(&self) -> AnalysisCacheStats {
+        self.state.lock().stats
+    }
+
+    /// Drop every entry (counters are kept)
+    pub fn clear(&self) {
+        let mut state = self.state.lock();
+        state.entries.clear();
+        state.stats.entries = 0;
+        state.stats.bytes = 0;
+    }
+
+    /// Zero the hit/miss/eviction counters
+    pub fn reset_stats(&self) {
+        let mut state = self.state.lock();
+        state.stats = AnalysisCacheStats {
+            entries: state.stats.entries,
+            bytes: state.stats.bytes,
+            ..AnalysisCacheStats::default()
+        };
+    }
+}
+
+impl Default for AnalysisCache {
+    fn default() -> Self {
+        Self::new(AnalysisCacheConfig::default())
+    }
+}
+
+static ANALYSIS_CACHE: OnceLock<AnalysisCache> = OnceLock::new();
+
+/// Get the global cache used by [`CharCodeData::analyze`]
+pub fn get_analysis_cache() -> &'static AnalysisCache {
+    ANALYSIS_CACHE.get_or_init(AnalysisCache::default)
+}
+
+// ============================================================================
+// JSON OUTPUT
+// ============================================================================
+
+/// Versioned JSON envelope for a [`CharCodeData`] analysis
+///
+/// Character codes are Unicode scalar values; undecodable bytes from
+/// [`CharCodeData::analyze_bytes`] appear as `0x110000 + byte`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AnalysisDocument {
+    /// Always [`ANALYSIS_SCHEMA_VERSION`] when produced by this crate
+    pub schema_version: u32,
+
+    /// The analysis itself
+    pub analysis: CharCodeData,
+}
+
+/// Errors reading an [`AnalysisDocument`]
+#[derive(Debug)]
+pub enum AnalysisJsonError {
+    /// The document was written with an incompatible schema version
+    UnsupportedVersion { found: u32, supported: u32 },
+
+    /// The JSON is malformed or does not match the schema
+    Json(serde_json::Error),
+}
+
+impl std::fmt::Display for AnalysisJsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedVersion { found, supported } => write!(
+                f,
+                "analysis schema version {found} is not supported (expected {supported})"
+            ),
+            Self::Json(e) => write!(f, "invalid analysis JSON: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisJsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Json(e) => Some(e),
+            Self::UnsupportedVersion { .. } => None,
+        }
+    }
+}
+
+impl CharCodeData {
+    /// Serialize as a versioned JSON document for MCP clients
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kodegen_utils::char_analysis::{ANALYSIS_SCHEMA_VERSION, CharCodeData};
+    ///
+    /// let json = CharCodeData::analyze("a\tb", "a b").to_json();
+    /// assert_eq!(json["schema_version"], ANALYSIS_SCHEMA_VERSION);
+    /// assert_eq!(json["analysis"]["whitespace_issues"][0], "tabs_vs_spaces");
+    /// ```
+    #[must_use]
+    pub fn to_json(&self) -> serde_json::Value {
+        let document = AnalysisDocument {
+            schema_version: ANALYSIS_SCHEMA_VERSION,
+            analysis: self.clone(),
+        };
+        // Serializing plain data with string keys cannot fail
+        serde_json::to_value(document).unwrap_or_default()
+    }
+
+    /// Read a document produced by [`CharCodeData::to_json`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AnalysisJsonError::UnsupportedVersion`] for documents with a
+    /// different schema version, and [`AnalysisJsonError::Json`] if the
+    /// document does not match the schema.
+    ///
+    /// Version 1 documents are rejected rather than upgraded: their
+    /// `has_composed` flag was also set for plain ASCII, and the correct
+    /// value cannot be recovered without the original texts.
+    pub fn from_json(value: serde_json::Value) -> Result<Self, AnalysisJsonError> {
+        let document: AnalysisDocument =
+            serde_json::from_value(value).map_err(AnalysisJsonError::Json)?;
+        if document.schema_version != ANALYSIS_SCHEMA_VERSION {
+            return Err(AnalysisJsonError::UnsupportedVersion {
+                found: document.schema_version,
+                supported: ANALYSIS_SCHEMA_VERSION,
+            });
+        }
+        Ok(document.analysis)
+    }
+
+    /// JSON Schema describing [`CharCodeData::to_json`] output
+    #[must_use]
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(AnalysisDocument)).unwrap_or_default()
+    }
+}
+
+// ============================================================================
+// SANITIZERS
+// ============================================================================
+
+/// Whether `c` is a bidirectional control character
+#[must_use]
+pub fn is_bidi_control(c: char) -> bool {
+    BIDI_CONTROL_CHARS.contains(&u32::from(c))
+}
+
+/// Remove default-ignorable (invisible) characters, leaving whitespace as-is
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::char_analysis::strip_invisible_chars;
+///
+/// assert_eq!(strip_invisible_chars("get\u{200B}User\u{00AD}Data\u{00A0}()"), "getUserData\u{00A0}()");
+/// ```
+#[must_use]
+pub fn strip_invisible_chars(text: &str) -> String {
+    text.chars()
+        .filter(|&c| !is_default_ignorable(u32::from(c)))
+        .collect()
+}
+
+/// Map exotic whitespace to ASCII and drop invisible characters
+///
+/// Unicode spaces (NBSP, EM SPACE, IDEOGRAPHIC SPACE, ...) become `' '`,
+/// NEL/LS/PS become `'\n'`, and default-ignorable characters (zero-width
+/// spaces and joiners, soft hyphens, variation selectors, bidi controls, ...)
+/// are removed. Note that removing ZWJ and variation selectors also splits
+/// emoji sequences into their component emoji.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::char_analysis::normalize_invisible_chars;
+///
+/// assert_eq!(
+///     normalize_invisible_chars("let\u{00A0}x\u{2003}=\u{200B} 1;\u{2028}"),
+///     "let x = 1;\n"
+/// );
+/// ```
+#[must_use]
+pub fn normalize_invisible_chars(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match u32::from(c) {
+            0x85 | 0x2028 | 0x2029 => Some('\n'),
+            code if is_unicode_whitespace(code) && !c.is_ascii() => Some(' '),
+            code if is_default_ignorable(code) => None,
+            _ => Some(c),
+        })
+        .collect()
+}
+
+/// Remove all bidirectional control characters from `text`
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::char_analysis::strip_bidi_controls;
+///
+/// let trojan = "if access_level != \"user\u{202E} \u{2066}// Check if admin\u{2069} \u{2066}\" {";
+/// assert_eq!(
+///     strip_bidi_controls(trojan),
+///     "if access_level != \"user // Check if admin \" {"
+/// );
+/// ```
+#[must_use]
+pub fn strip_bidi_controls(text: &str) -> String {
+    text.chars().filter(|&c| !is_bidi_control(c)).collect()
+}
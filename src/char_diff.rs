@@ -2,6 +2,12 @@
 //!
 //! Provides visual diff in format: `prefix{-removed-}{+added+}suffix`
 
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::hash::Hash;
+use std::ops::Range;
+
 /// Character-level diff result
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CharDiff {
@@ -99,3 +105,1319 @@ impl CharDiff {
         self.expected_part.trim() == self.actual_part.trim()
     }
 }
+
+// ============================================================================
+// EDIT SCRIPT
+// ============================================================================
+
+/// Default cost of an empty edit operation for [`cleanup_efficiency`]
+pub const DEFAULT_EDIT_COST: usize = 4;
+
+/// Kind of a single diff operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Text present in both strings
+    Equal,
+    /// Text only present in the expected string
+    Delete,
+    /// Text only present in the actual string
+    Insert,
+}
+
+/// A run of text with the same diff kind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOp {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+impl DiffOp {
+    #[must_use]
+    pub fn new(kind: DiffKind, text: impl Into<String>) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn equal(text: impl Into<String>) -> Self {
+        Self::new(DiffKind::Equal, text)
+    }
+
+    #[must_use]
+    pub fn delete(text: impl Into<String>) -> Self {
+        Self::new(DiffKind::Delete, text)
+    }
+
+    #[must_use]
+    pub fn insert(text: impl Into<String>) -> Self {
+        Self::new(DiffKind::Insert, text)
+    }
+}
+
+impl CharDiff {
+    /// Minimal character-level edit script for this diff
+    ///
+    /// The common prefix and suffix become equalities around the edits
+    /// found inside the differing middle.
+    #[must_use]
+    pub fn ops(&self) -> Vec<DiffOp> {
+        let mut ops = vec![DiffOp::equal(self.common_prefix.as_str())];
+        ops.extend(diff_chars(&self.expected_part, &self.actual_part));
+        ops.push(DiffOp::equal(self.common_suffix.as_str()));
+        cleanup_merge(&mut ops);
+        ops
+    }
+
+    /// Edit script after [`cleanup_semantic`], suited for display to agents
+    #[must_use]
+    pub fn semantic_ops(&self) -> Vec<DiffOp> {
+        let mut ops = self.ops();
+        cleanup_semantic(&mut ops);
+        ops
+    }
+
+    /// Format the semantically cleaned edit script
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kodegen_utils::char_diff::CharDiff;
+    ///
+    /// let diff = CharDiff::new("The cat sat", "The dog sat");
+    /// assert_eq!(diff.format_semantic(), "The {-cat-}{+dog+} sat");
+    /// ```
+    #[must_use]
+    pub fn format_semantic(&self) -> String {
+        format_ops(&self.semantic_ops())
+    }
+}
+
+/// Format an edit script as `equal{-removed-}{+added+}equal...`
+#[must_use]
+pub fn format_ops(ops: &[DiffOp]) -> String {
+    let mut output = String::new();
+    for op in ops {
+        match op.kind {
+            DiffKind::Equal => output.push_str(&op.text),
+            DiffKind::Delete => {
+                output.push_str("{-");
+                output.push_str(&op.text);
+                output.push_str("-}");
+            }
+            DiffKind::Insert => {
+                output.push_str("{+");
+                output.push_str(&op.text);
+                output.push_str("+}");
+            }
+        }
+    }
+    output
+}
+
+/// Compute a minimal character-level edit script (Myers' O(ND) algorithm)
+///
+/// Deletions always precede insertions within a change, and adjacent
+/// operations of the same kind are merged.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::char_diff::{diff_chars, format_ops};
+///
+/// let ops = diff_chars("cats", "katz");
+/// assert_eq!(format_ops(&ops), "{-c-}{+k+}at{-s-}{+z+}");
+/// ```
+#[must_use]
+pub fn diff_chars(expected: &str, actual: &str) -> Vec<DiffOp> {
+    let a: Vec<char> = expected.chars().collect();
+    let b: Vec<char> = actual.chars().collect();
+
+    let mut ops = Vec::new();
+    let (mut ai, mut bi) = (0, 0);
+    for (kind, len) in diff_slices(&a, &b) {
+        let text: String = match kind {
+            DiffKind::Equal | DiffKind::Delete => a[ai..ai + len].iter().collect(),
+            DiffKind::Insert => b[bi..bi + len].iter().collect(),
+        };
+        match kind {
+            DiffKind::Equal => {
+                ai += len;
+                bi += len;
+            }
+            DiffKind::Delete => ai += len,
+            DiffKind::Insert => bi += len,
+        }
+        ops.push(DiffOp::new(kind, text));
+    }
+
+    cleanup_merge(&mut ops);
+    ops
+}
+
+/// Diff two slices, returning runs of `(kind, length)`
+///
+/// Shared by the character and line diffs. Runs are in order; a run of
+/// deletions consumes `a`, insertions consume `b`, equalities consume both.
+pub(crate) fn diff_slices<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(DiffKind, usize)> {
+    let mut runs = Vec::new();
+    diff_slices_into(a, b, &mut runs);
+    runs
+}
+
+fn diff_slices_into<T: PartialEq>(a: &[T], b: &[T], runs: &mut Vec<(DiffKind, usize)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    push_run(runs, DiffKind::Equal, prefix);
+    diff_middle(
+        &a[prefix..a.len() - suffix],
+        &b[prefix..b.len() - suffix],
+        runs,
+    );
+    push_run(runs, DiffKind::Equal, suffix);
+}
+
+/// Diff two slices that share no common prefix or suffix
+fn diff_middle<T: PartialEq>(a: &[T], b: &[T], runs: &mut Vec<(DiffKind, usize)>) {
+    if a.is_empty() {
+        push_run(runs, DiffKind::Insert, b.len());
+        return;
+    }
+    if b.is_empty() {
+        push_run(runs, DiffKind::Delete, a.len());
+        return;
+    }
+
+    // Shorter slice contained in the longer one: a single edit on each side
+    let (long, short, long_kind) = if a.len() > b.len() {
+        (a, b, DiffKind::Delete)
+    } else {
+        (b, a, DiffKind::Insert)
+    };
+    if let Some(pos) = long.windows(short.len()).position(|w| w == short) {
+        push_run(runs, long_kind, pos);
+        push_run(runs, DiffKind::Equal, short.len());
+        push_run(runs, long_kind, long.len() - pos - short.len());
+        return;
+    }
+    if short.len() == 1 {
+        push_run(runs, DiffKind::Delete, a.len());
+        push_run(runs, DiffKind::Insert, b.len());
+        return;
+    }
+
+    bisect(a, b, runs);
+}
+
+/// Find the middle snake of a diff, split there and recurse
+///
+/// Linear-space variant of Myers' algorithm: walks forward and reverse
+/// paths simultaneously until they overlap.
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn bisect<T: PartialEq>(a: &[T], b: &[T], runs: &mut Vec<(DiffKind, usize)>) {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max_d = (n + m + 1) / 2;
+    let v_offset = max_d;
+    let v_length = 2 * max_d;
+    let mut v1 = vec![-1_isize; v_length as usize];
+    let mut v2 = vec![-1_isize; v_length as usize];
+    v1[(v_offset + 1) as usize] = 0;
+    v2[(v_offset + 1) as usize] = 0;
+
+    let delta = n - m;
+    // If the total number of characters is odd, the front path collides with the reverse path
+    let front = delta % 2 != 0;
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+
+    for d in 0..max_d {
+        // Walk the front path one step
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let k1_offset = (v_offset + k1) as usize;
+            let mut x1 = if k1 == -d || (k1 != d && v1[k1_offset - 1] < v1[k1_offset + 1]) {
+                v1[k1_offset + 1]
+            } else {
+                v1[k1_offset - 1] + 1
+            };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && a[x1 as usize] == b[y1 as usize] {
+                x1 += 1;
+                y1 += 1;
+            }
+            v1[k1_offset] = x1;
+            if x1 > n {
+                k1_end += 2;
+            } else if y1 > m {
+                k1_start += 2;
+            } else if front {
+                let k2_offset = v_offset + delta - k1;
+                if k2_offset >= 0 && k2_offset < v_length && v2[k2_offset as usize] != -1 {
+                    let x2 = n - v2[k2_offset as usize];
+                    if x1 >= x2 {
+                        bisect_split(a, b, x1 as usize, y1 as usize, runs);
+                        return;
+                    }
+                }
+            }
+            k1 += 2;
+        }
+
+        // Walk the reverse path one step
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let k2_offset = (v_offset + k2) as usize;
+            let mut x2 = if k2 == -d || (k2 != d && v2[k2_offset - 1] < v2[k2_offset + 1]) {
+                v2[k2_offset + 1]
+            } else {
+                v2[k2_offset - 1] + 1
+            };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m && a[(n - x2 - 1) as usize] == b[(m - y2 - 1) as usize] {
+                x2 += 1;
+                y2 += 1;
+            }
+            v2[k2_offset] = x2;
+            if x2 > n {
+                k2_end += 2;
+            } else if y2 > m {
+                k2_start += 2;
+            } else if !front {
+                let k1_offset = v_offset + delta - k2;
+                if k1_offset >= 0 && k1_offset < v_length && v1[k1_offset as usize] != -1 {
+                    let x1 = v1[k1_offset as usize];
+                    let y1 = v_offset + x1 - k1_offset;
+                    if x1 >= n - x2 {
+                        bisect_split(a, b, x1 as usize, y1 as usize, runs);
+                        return;
+                    }
+                }
+            }
+            k2 += 2;
+        }
+    }
+
+    // No commonality at all
+    push_run(runs, DiffKind::Delete, a.len());
+    push_run(runs, DiffKind::Insert, b.len());
+}
+
+fn bisect_split<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    x: usize,
+    y: usize,
+    runs: &mut Vec<(DiffKind, usize)>,
+) {
+    diff_slices_into(&a[..x], &b[..y], runs);
+    diff_slices_into(&a[x..], &b[y..], runs);
+}
+
+/// Append a run, merging with the previous run of the same kind
+fn push_run(runs: &mut Vec<(DiffKind, usize)>, kind: DiffKind, len: usize) {
+    if len == 0 {
+        return;
+    }
+    match runs.last_mut() {
+        Some((last_kind, last_len)) if *last_kind == kind => *last_len += len,
+        _ => runs.push((kind, len)),
+    }
+}
+
+// ============================================================================
+// CLEANUP PASSES
+// ============================================================================
+
+/// Reorder and merge like edit sections, merging equalities
+///
+/// Any edit section can move as long as it doesn't cross an equality.
+/// Common text at the edges of paired deletions/insertions is factored out
+/// into the surrounding equalities.
+pub fn cleanup_merge(ops: &mut Vec<DiffOp>) {
+    ops.retain(|op| !op.text.is_empty());
+
+    let mut merged: Vec<DiffOp> = Vec::with_capacity(ops.len());
+    let mut text_delete = String::new();
+    let mut text_insert = String::new();
+
+    // Flush the pending edit section, factoring common affixes into equalities
+    let flush = |merged: &mut Vec<DiffOp>,
+                 text_delete: &mut String,
+                 text_insert: &mut String,
+                 next_equal: &mut String| {
+        if !text_delete.is_empty() && !text_insert.is_empty() {
+            let prefix = CharDiff::find_common_prefix(text_insert, text_delete);
+            if prefix > 0 {
+                let common = text_insert[..prefix].to_string();
+                match merged.last_mut() {
+                    Some(last) if last.kind == DiffKind::Equal => last.text.push_str(&common),
+                    _ => merged.push(DiffOp::equal(common)),
+                }
+                text_insert.drain(..prefix);
+                text_delete.drain(..prefix);
+            }
+            let suffix = CharDiff::find_common_suffix(text_insert, text_delete, 0);
+            if suffix > 0 {
+                let common = text_insert.split_off(text_insert.len() - suffix);
+                text_delete.truncate(text_delete.len() - suffix);
+                next_equal.insert_str(0, &common);
+            }
+        }
+        if !text_delete.is_empty() {
+            merged.push(DiffOp::delete(std::mem::take(text_delete)));
+        }
+        if !text_insert.is_empty() {
+            merged.push(DiffOp::insert(std::mem::take(text_insert)));
+        }
+    };
+
+    for op in ops.drain(..) {
+        match op.kind {
+            DiffKind::Delete => text_delete.push_str(&op.text),
+            DiffKind::Insert => text_insert.push_str(&op.text),
+            DiffKind::Equal => {
+                let mut equal = op.text;
+                flush(&mut merged, &mut text_delete, &mut text_insert, &mut equal);
+                match merged.last_mut() {
+                    Some(last) if last.kind == DiffKind::Equal => last.text.push_str(&equal),
+                    _ => merged.push(DiffOp::equal(equal)),
+                }
+            }
+        }
+    }
+    let mut trailing = String::new();
+    flush(
+        &mut merged,
+        &mut text_delete,
+        &mut text_insert,
+        &mut trailing,
+    );
+    if !trailing.is_empty() {
+        merged.push(DiffOp::equal(trailing));
+    }
+    *ops = merged;
+
+    // Second pass: shift single edits surrounded by equalities sideways to
+    // eliminate an equality, e.g. A<ins>BA</ins>C -> <ins>AB</ins>AC
+    let mut changes = false;
+    let mut pointer = 1;
+    while pointer + 1 < ops.len() {
+        if ops[pointer - 1].kind == DiffKind::Equal && ops[pointer + 1].kind == DiffKind::Equal {
+            let prev = ops[pointer - 1].text.clone();
+            let next = ops[pointer + 1].text.clone();
+            if ops[pointer].text.ends_with(prev.as_str()) {
+                let edit = &ops[pointer].text;
+                ops[pointer].text = format!("{prev}{}", &edit[..edit.len() - prev.len()]);
+                ops[pointer + 1].text = format!("{prev}{next}");
+                ops.remove(pointer - 1);
+                changes = true;
+            } else if ops[pointer].text.starts_with(next.as_str()) {
+                ops[pointer - 1].text.push_str(&next);
+                let edit = &ops[pointer].text;
+                ops[pointer].text = format!("{}{next}", &edit[next.len()..]);
+                ops.remove(pointer + 1);
+                changes = true;
+            }
+        }
+        pointer += 1;
+    }
+
+    if changes {
+        cleanup_merge(ops);
+    }
+}
+
+/// Eliminate semantically trivial equalities and align edits to word boundaries
+///
+/// Short equalities sandwiched between larger edits are folded into the
+/// edits, so `{-m-}{+s+}o{-u-}{+fa+}s{-e-}` becomes `{-mouse-}{+sofas+}`.
+/// Afterwards [`cleanup_semantic_lossless`] shifts the remaining edits to
+/// line and word boundaries, and overlapping deletions/insertions are
+/// split around their shared text.
+pub fn cleanup_semantic(ops: &mut Vec<DiffOp>) {
+    let mut changes = false;
+    // Indices of equalities seen so far
+    let mut equalities: Vec<usize> = Vec::new();
+    let mut last_equality: Option<String> = None;
+    // Edit lengths before (1) and after (2) the last equality
+    let (mut ins1, mut del1, mut ins2, mut del2) = (0, 0, 0, 0);
+
+    let mut pointer = 0;
+    while pointer < ops.len() {
+        if ops[pointer].kind == DiffKind::Equal {
+            equalities.push(pointer);
+            ins1 = ins2;
+            del1 = del2;
+            ins2 = 0;
+            del2 = 0;
+            last_equality = Some(ops[pointer].text.clone());
+        } else {
+            let len = char_len(&ops[pointer].text);
+            if ops[pointer].kind == DiffKind::Insert {
+                ins2 += len;
+            } else {
+                del2 += len;
+            }
+
+            // Eliminate an equality that is smaller or equal to the edits on both sides
+            if let Some(equality) = last_equality.as_deref()
+                && !equality.is_empty()
+                && char_len(equality) <= ins1.max(del1)
+                && char_len(equality) <= ins2.max(del2)
+                && let Some(&index) = equalities.last()
+            {
+                let text = std::mem::take(&mut ops[index].text);
+                ops[index] = DiffOp::delete(text.clone());
+                ops.insert(index + 1, DiffOp::insert(text));
+
+                // Throw away this equality and the previous one; it needs re-evaluation
+                equalities.pop();
+                equalities.pop();
+                ins1 = 0;
+                del1 = 0;
+                ins2 = 0;
+                del2 = 0;
+                last_equality = None;
+                changes = true;
+
+                match equalities.last() {
+                    Some(&previous) => pointer = previous,
+                    None => {
+                        pointer = 0;
+                        continue;
+                    }
+                }
+            }
+        }
+        pointer += 1;
+    }
+
+    if changes {
+        cleanup_merge(ops);
+    }
+    cleanup_semantic_lossless(ops);
+
+    // Extract overlaps between deletions and insertions, e.g.
+    // <del>abcxxx</del><ins>xxxdef</ins> -> <del>abc</del>xxx<ins>def</ins>
+    let mut pointer = 1;
+    while pointer < ops.len() {
+        if ops[pointer - 1].kind == DiffKind::Delete && ops[pointer].kind == DiffKind::Insert {
+            let deletion: Vec<char> = ops[pointer - 1].text.chars().collect();
+            let insertion: Vec<char> = ops[pointer].text.chars().collect();
+            let overlap1 = common_overlap(&deletion, &insertion);
+            let overlap2 = common_overlap(&insertion, &deletion);
+
+            if overlap1 >= overlap2 {
+                if overlap1 * 2 >= deletion.len() || overlap1 * 2 >= insertion.len() {
+                    ops.insert(
+                        pointer,
+                        DiffOp::equal(insertion[..overlap1].iter().collect::<String>()),
+                    );
+                    ops[pointer - 1].text = deletion[..deletion.len() - overlap1].iter().collect();
+                    ops[pointer + 1].text = insertion[overlap1..].iter().collect();
+                    pointer += 1;
+                }
+            } else if overlap2 * 2 >= deletion.len() || overlap2 * 2 >= insertion.len() {
+                // Reverse overlap: swap the edit order around the shared text
+                ops.insert(
+                    pointer,
+                    DiffOp::equal(deletion[..overlap2].iter().collect::<String>()),
+                );
+                ops[pointer - 1] = DiffOp::insert(
+                    insertion[..insertion.len() - overlap2]
+                        .iter()
+                        .collect::<String>(),
+                );
+                ops[pointer + 1] = DiffOp::delete(deletion[overlap2..].iter().collect::<String>());
+                pointer += 1;
+            }
+            pointer += 1;
+        }
+        pointer += 1;
+    }
+    ops.retain(|op| !op.text.is_empty());
+}
+
+/// Shift single edits surrounded by equalities to line and word boundaries
+///
+/// Does not change what the edit script produces, only where the edit
+/// boundaries fall: `The c{+ow and the c+}at.` becomes
+/// `The {+cow and the +}cat.`
+pub fn cleanup_semantic_lossless(ops: &mut Vec<DiffOp>) {
+    let mut pointer = 1;
+    while pointer + 1 < ops.len() {
+        if ops[pointer - 1].kind == DiffKind::Equal && ops[pointer + 1].kind == DiffKind::Equal {
+            let mut equality1: Vec<char> = ops[pointer - 1].text.chars().collect();
+            let mut edit: Vec<char> = ops[pointer].text.chars().collect();
+            let mut equality2: Vec<char> = ops[pointer + 1].text.chars().collect();
+
+            // First, shift the edit as far left as possible
+            let common = equality1
+                .iter()
+                .rev()
+                .zip(edit.iter().rev())
+                .take_while(|(x, y)| x == y)
+                .count();
+            if common > 0 {
+                let shared = edit[edit.len() - common..].to_vec();
+                equality1.truncate(equality1.len() - common);
+                edit.truncate(edit.len() - common);
+                edit.splice(0..0, shared.iter().copied());
+                equality2.splice(0..0, shared);
+            }
+
+            // Second, step character by character right, looking for the best fit
+            let mut best = (equality1.clone(), edit.clone(), equality2.clone());
+            let mut best_score =
+                semantic_score(&equality1, &edit) + semantic_score(&edit, &equality2);
+            while !edit.is_empty() && !equality2.is_empty() && edit[0] == equality2[0] {
+                equality1.push(edit.remove(0));
+                edit.push(equality2.remove(0));
+                let score = semantic_score(&equality1, &edit) + semantic_score(&edit, &equality2);
+                // The >= encourages trailing rather than leading whitespace on edits
+                if score >= best_score {
+                    best_score = score;
+                    best = (equality1.clone(), edit.clone(), equality2.clone());
+                }
+            }
+
+            let (best1, best_edit, best2) = best;
+            let best1: String = best1.into_iter().collect();
+            if ops[pointer - 1].text != best1 {
+                // We have an improvement, save it back to the edit script
+                if best1.is_empty() {
+                    ops.remove(pointer - 1);
+                    pointer -= 1;
+                } else {
+                    ops[pointer - 1].text = best1;
+                }
+                ops[pointer].text = best_edit.into_iter().collect();
+                if best2.is_empty() {
+                    ops.remove(pointer + 1);
+                    pointer -= 1;
+                } else {
+                    ops[pointer + 1].text = best2.into_iter().collect();
+                }
+            }
+        }
+        pointer += 1;
+    }
+}
+
+/// Reduce the number of edits by eliminating operationally trivial equalities
+///
+/// An equality shorter than `edit_cost` characters that is surrounded by
+/// edits is cheaper to express as part of those edits. Use
+/// [`DEFAULT_EDIT_COST`] unless the consumer has a specific cost model.
+pub fn cleanup_efficiency(ops: &mut Vec<DiffOp>, edit_cost: usize) {
+    let mut changes = false;
+    let mut equalities: Vec<usize> = Vec::new();
+    let mut last_equality: Option<String> = None;
+    // Whether there is an insertion/deletion before (pre) and after (post) the last equality
+    let (mut pre_ins, mut pre_del, mut post_ins, mut post_del) = (false, false, false, false);
+
+    let mut pointer = 0;
+    while pointer < ops.len() {
+        if ops[pointer].kind == DiffKind::Equal {
+            if char_len(&ops[pointer].text) < edit_cost && (post_ins || post_del) {
+                // Candidate found
+                equalities.push(pointer);
+                pre_ins = post_ins;
+                pre_del = post_del;
+                last_equality = Some(ops[pointer].text.clone());
+            } else {
+                // Not a candidate, and can never become one
+                equalities.clear();
+                last_equality = None;
+            }
+            post_ins = false;
+            post_del = false;
+        } else {
+            if ops[pointer].kind == DiffKind::Delete {
+                post_del = true;
+            } else {
+                post_ins = true;
+            }
+
+            // Five types to be split:
+            // <ins>A</ins><del>B</del>XY<ins>C</ins><del>D</del>
+            // <ins>A</ins>X<ins>C</ins><del>D</del>
+            // <ins>A</ins><del>B</del>X<ins>C</ins>
+            // <del>A</del>X<ins>C</ins><del>D</del>
+            // <ins>A</ins><del>B</del>X<del>C</del>
+            let sides = [pre_ins, pre_del, post_ins, post_del]
+                .iter()
+                .filter(|&&side| side)
+                .count();
+            if let Some(equality) = last_equality.as_deref()
+                && !equality.is_empty()
+                && (sides == 4 || (char_len(equality) * 2 < edit_cost && sides == 3))
+                && let Some(&index) = equalities.last()
+            {
+                let text = std::mem::take(&mut ops[index].text);
+                ops[index] = DiffOp::delete(text.clone());
+                ops.insert(index + 1, DiffOp::insert(text));
+                equalities.pop();
+                last_equality = None;
+                changes = true;
+
+                if pre_ins && pre_del {
+                    // No changes made which could affect previous entry, keep going
+                    post_ins = true;
+                    post_del = true;
+                    equalities.clear();
+                } else {
+                    equalities.pop();
+                    post_ins = false;
+                    post_del = false;
+                    match equalities.last() {
+                        Some(&previous) => pointer = previous,
+                        None => {
+                            pointer = 0;
+                            continue;
+                        }
+                    }
+                }
+            }
+        }
+        pointer += 1;
+    }
+
+    if changes {
+        cleanup_merge(ops);
+    }
+}
+
+/// Score how well an edit boundary between `one` and `two` falls on a
+/// natural boundary (higher is better, 6 is best)
+fn semantic_score(one: &[char], two: &[char]) -> u8 {
+    let (Some(&char1), Some(&char2)) = (one.last(), two.first()) else {
+        // Edges are the best
+        return 6;
+    };
+
+    let non_alnum1 = !char1.is_alphanumeric();
+    let non_alnum2 = !char2.is_alphanumeric();
+    let whitespace1 = non_alnum1 && char1.is_whitespace();
+    let whitespace2 = non_alnum2 && char2.is_whitespace();
+    let line_break1 = matches!(char1, '\r' | '\n');
+    let line_break2 = matches!(char2, '\r' | '\n');
+    let blank_line1 = line_break1 && ends_with_blank_line(one);
+    let blank_line2 = line_break2 && starts_with_blank_line(two);
+
+    if blank_line1 || blank_line2 {
+        5
+    } else if line_break1 || line_break2 {
+        4
+    } else if non_alnum1 && !whitespace1 && whitespace2 {
+        // End of sentence
+        3
+    } else if whitespace1 || whitespace2 {
+        2
+    } else if non_alnum1 || non_alnum2 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Matches `\n\r?\n$`
+fn ends_with_blank_line(chars: &[char]) -> bool {
+    matches!(chars, [.., '\n', '\n'] | [.., '\n', '\r', '\n'])
+}
+
+/// Matches `^\r?\n\r?\n`
+fn starts_with_blank_line(chars: &[char]) -> bool {
+    matches!(
+        chars,
+        ['\n', '\n', ..]
+            | ['\r', '\n', '\n', ..]
+            | ['\n', '\r', '\n', ..]
+            | ['\r', '\n', '\r', '\n', ..]
+    )
+}
+
+/// Length of the longest suffix of `one` that is a prefix of `two`
+fn common_overlap(one: &[char], two: &[char]) -> usize {
+    let max = one.len().min(two.len());
+    (1..=max)
+        .rev()
+        .find(|&len| one[one.len() - len..] == two[..len])
+        .unwrap_or(0)
+}
+
+/// Length of a string in characters
+fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+// ============================================================================
+// LINE DIFF
+// ============================================================================
+
+/// Compute a line-level edit script
+///
+/// Lines keep their terminators, so concatenating the equal and deleted
+/// texts reproduces `expected` exactly (likewise equal and inserted texts
+/// for `actual`). Each op covers one or more whole lines.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::char_diff::{diff_lines, format_ops};
+///
+/// let ops = diff_lines("a\nb\nc\n", "a\nB\nc\n");
+/// assert_eq!(format_ops(&ops), "a\n{-b\n-}{+B\n+}c\n");
+/// ```
+#[must_use]
+pub fn diff_lines(expected: &str, actual: &str) -> Vec<DiffOp> {
+    let a = split_lines(expected);
+    let b = split_lines(actual);
+
+    let mut ops = Vec::new();
+    let (mut ai, mut bi) = (0, 0);
+    for (kind, len) in diff_slices(&a, &b) {
+        let text = match kind {
+            DiffKind::Equal | DiffKind::Delete => a[ai..ai + len].concat(),
+            DiffKind::Insert => b[bi..bi + len].concat(),
+        };
+        match kind {
+            DiffKind::Equal => {
+                ai += len;
+                bi += len;
+            }
+            DiffKind::Delete => ai += len,
+            DiffKind::Insert => bi += len,
+        }
+        ops.push(DiffOp::new(kind, text));
+    }
+    ops
+}
+
+/// Diff two line slices with patience anchoring, returning `(kind, length)` runs
+///
+/// Lines unique to both sides are aligned by their longest increasing
+/// subsequence; the gaps between anchors fall back to [`diff_slices`].
+/// Ambiguous hunks are then slid as far down as possible, so a block that
+/// ends in a repeated line like `}` keeps that line with the block.
+pub(crate) fn diff_line_slices<T: Eq + Hash>(a: &[T], b: &[T]) -> Vec<(DiffKind, usize)> {
+    let mut runs = Vec::new();
+    patience_into(a, b, &mut runs);
+    slide_down(a, b, &mut runs);
+    runs
+}
+
+/// Slide each edit run between two equalities down while the first line
+/// of the run equals the line after it
+///
+/// The result describes the same edit; only which copy of a repeated line
+/// counts as unchanged differs.
+fn slide_down<T: PartialEq>(a: &[T], b: &[T], runs: &mut Vec<(DiffKind, usize)>) {
+    let (mut ai, mut bi) = (0, 0);
+    let mut index = 0;
+    while index < runs.len() {
+        let (kind, len) = runs[index];
+        if kind == DiffKind::Equal {
+            ai += len;
+            bi += len;
+            index += 1;
+            continue;
+        }
+
+        let after_equal = index == 0 || runs[index - 1].0 == DiffKind::Equal;
+        let before_equal = runs
+            .get(index + 1)
+            .is_some_and(|run| run.0 == DiffKind::Equal);
+        if after_equal && before_equal {
+            let (seq, start) = if kind == DiffKind::Delete {
+                (a, ai)
+            } else {
+                (b, bi)
+            };
+            let available = runs[index + 1].1;
+            let shift = (0..available)
+                .take_while(|&k| seq[start + k] == seq[start + len + k])
+                .count();
+            if shift > 0 {
+                if index == 0 {
+                    runs.insert(0, (DiffKind::Equal, shift));
+                    index += 1;
+                } else {
+                    runs[index - 1].1 += shift;
+                }
+                runs[index + 1].1 -= shift;
+                if runs[index + 1].1 == 0 {
+                    runs.remove(index + 1);
+                }
+                ai += shift;
+                bi += shift;
+            }
+        }
+
+        if kind == DiffKind::Delete {
+            ai += len;
+        } else {
+            bi += len;
+        }
+        index += 1;
+    }
+}
+
+fn patience_into<T: Eq + Hash>(a: &[T], b: &[T], runs: &mut Vec<(DiffKind, usize)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    push_run(runs, DiffKind::Equal, prefix);
+
+    let anchors = unique_anchors(a_mid, b_mid);
+    if anchors.is_empty() {
+        for (kind, len) in diff_slices(a_mid, b_mid) {
+            push_run(runs, kind, len);
+        }
+    } else {
+        let (mut ai, mut bi) = (0, 0);
+        for (anchor_a, anchor_b) in anchors {
+            patience_into(&a_mid[ai..anchor_a], &b_mid[bi..anchor_b], runs);
+            push_run(runs, DiffKind::Equal, 1);
+            ai = anchor_a + 1;
+            bi = anchor_b + 1;
+        }
+        patience_into(&a_mid[ai..], &b_mid[bi..], runs);
+    }
+
+    push_run(runs, DiffKind::Equal, suffix);
+}
+
+/// Positions of lines unique in both slices, reduced to the longest
+/// sequence that is increasing in both
+fn unique_anchors<T: Eq + Hash>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    // line -> (count in a, index in a, count in b, index in b)
+    let mut counts: HashMap<&T, (usize, usize, usize, usize)> = HashMap::new();
+    for (i, line) in a.iter().enumerate() {
+        let entry = counts.entry(line).or_insert((0, i, 0, 0));
+        entry.0 += 1;
+    }
+    for (j, line) in b.iter().enumerate() {
+        if let Some(entry) = counts.get_mut(line) {
+            entry.2 += 1;
+            entry.3 = j;
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = counts
+        .into_values()
+        .filter(|&(count_a, _, count_b, _)| count_a == 1 && count_b == 1)
+        .map(|(_, i, _, j)| (i, j))
+        .collect();
+    pairs.sort_unstable();
+
+    // Longest increasing subsequence on the b positions (patience sorting)
+    let mut tails: Vec<usize> = Vec::new(); // index into pairs of the smallest tail per length
+    let mut previous: Vec<Option<usize>> = vec![None; pairs.len()];
+    for (index, &(_, j)) in pairs.iter().enumerate() {
+        let pile = tails.partition_point(|&tail| pairs[tail].1 < j);
+        previous[index] = pile.checked_sub(1).map(|p| tails[p]);
+        if pile == tails.len() {
+            tails.push(index);
+        } else {
+            tails[pile] = index;
+        }
+    }
+
+    let mut anchors = Vec::with_capacity(tails.len());
+    let mut cursor = tails.last().copied();
+    while let Some(index) = cursor {
+        anchors.push(pairs[index]);
+        cursor = previous[index];
+    }
+    anchors.reverse();
+    anchors
+}
+
+/// Split text into lines, keeping each line's `\n` terminator
+pub(crate) fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+// ============================================================================
+// HUNKS
+// ============================================================================
+
+/// A contiguous block of changed lines in a line diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    /// Zero-based line range in the expected text
+    pub old_lines: Range<usize>,
+
+    /// Zero-based line range in the actual text
+    pub new_lines: Range<usize>,
+
+    /// Removed lines, with terminators
+    pub old_text: String,
+
+    /// Added lines, with terminators
+    pub new_text: String,
+}
+
+/// What kind of change a hunk makes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HunkKind {
+    /// Only `\n` / `\r\n` / `\r` terminators differ, including a missing
+    /// final newline
+    LineEnding,
+    /// Only whitespace at the end of lines differs
+    TrailingWhitespace,
+    /// Only leading whitespace differs, line for line
+    Indentation,
+    /// Only whitespace between words differs, line for line
+    Spacing,
+    /// Same words, but line breaks moved
+    Reflow,
+    /// Anything else
+    Substantive,
+}
+
+impl HunkKind {
+    /// Whether the change is purely cosmetic and safe to collapse in review
+    #[must_use]
+    pub fn is_cosmetic(&self) -> bool {
+        !matches!(self, Self::Substantive)
+    }
+
+    /// Short human-readable description
+    #[must_use]
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::LineEnding => "line ending change",
+            Self::TrailingWhitespace => "trailing whitespace change",
+            Self::Indentation => "indentation change",
+            Self::Spacing => "spacing change",
+            Self::Reflow => "reflow (line breaks moved)",
+            Self::Substantive => "substantive change",
+        }
+    }
+}
+
+impl DiffHunk {
+    /// Classify this hunk
+    #[must_use]
+    pub fn kind(&self) -> HunkKind {
+        classify_change(&self.old_text, &self.new_text)
+    }
+}
+
+/// Group a line diff into hunks of adjacent changed lines
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::char_diff::{HunkKind, diff_hunks};
+///
+/// let hunks = diff_hunks("fn a() {\n  x();\n}\n", "fn a() {\n    x();\n}\n");
+/// assert_eq!(hunks.len(), 1);
+/// assert_eq!(hunks[0].kind(), HunkKind::Indentation);
+/// ```
+#[must_use]
+pub fn diff_hunks(expected: &str, actual: &str) -> Vec<DiffHunk> {
+    let a = split_lines(expected);
+    let b = split_lines(actual);
+
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let mut current: Option<DiffHunk> = None;
+    let (mut ai, mut bi) = (0, 0);
+    for (kind, len) in diff_line_slices(&a, &b) {
+        if kind == DiffKind::Equal {
+            hunks.extend(current.take());
+            ai += len;
+            bi += len;
+            continue;
+        }
+
+        let hunk = current.get_or_insert_with(|| DiffHunk {
+            old_lines: ai..ai,
+            new_lines: bi..bi,
+            old_text: String::new(),
+            new_text: String::new(),
+        });
+        if kind == DiffKind::Delete {
+            hunk.old_text.push_str(&a[ai..ai + len].concat());
+            ai += len;
+            hunk.old_lines.end = ai;
+        } else {
+            hunk.new_text.push_str(&b[bi..bi + len].concat());
+            bi += len;
+            hunk.new_lines.end = bi;
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+/// Classify the change from `old` to `new` by the narrowest category that
+/// explains all of it
+#[must_use]
+pub fn classify_change(old: &str, new: &str) -> HunkKind {
+    let old_lf = old.replace("\r\n", "\n").replace('\r', "\n");
+    let new_lf = new.replace("\r\n", "\n").replace('\r', "\n");
+    // Adding or removing the final newline only changes a terminator too;
+    // adding a blank line does not
+    let gains_final_newline =
+        |short: &str, long: &str| !short.ends_with('\n') && long.strip_suffix('\n') == Some(short);
+    if old_lf == new_lf
+        || gains_final_newline(&old_lf, &new_lf)
+        || gains_final_newline(&new_lf, &old_lf)
+    {
+        return HunkKind::LineEnding;
+    }
+
+    let old_lines: Vec<&str> = old_lf.lines().collect();
+    let new_lines: Vec<&str> = new_lf.lines().collect();
+    if old_lines.len() == new_lines.len() {
+        let trailing = old_lines
+            .iter()
+            .zip(&new_lines)
+            .all(|(o, n)| o.trim_end() == n.trim_end());
+        if trailing {
+            return HunkKind::TrailingWhitespace;
+        }
+
+        let indentation = old_lines
+            .iter()
+            .zip(&new_lines)
+            .all(|(o, n)| o.trim() == n.trim());
+        if indentation {
+            return HunkKind::Indentation;
+        }
+
+        let spacing = old_lines
+            .iter()
+            .zip(&new_lines)
+            .all(|(o, n)| o.split_whitespace().eq(n.split_whitespace()));
+        if spacing {
+            return HunkKind::Spacing;
+        }
+    }
+
+    if old_lf.split_whitespace().eq(new_lf.split_whitespace()) {
+        return HunkKind::Reflow;
+    }
+
+    HunkKind::Substantive
+}
+
+// ============================================================================
+// BYTE DIFF
+// ============================================================================
+
+/// One decoded unit of possibly invalid UTF-8
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteToken {
+    /// A validly encoded character
+    Char(char),
+    /// A byte that is not part of any valid UTF-8 sequence
+    Invalid(u8),
+}
+
+/// Decode bytes into characters, keeping undecodable bytes individually
+#[must_use]
+pub fn decode_bytes(bytes: &[u8]) -> Vec<ByteToken> {
+    let mut tokens = Vec::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        tokens.extend(chunk.valid().chars().map(ByteToken::Char));
+        tokens.extend(chunk.invalid().iter().copied().map(ByteToken::Invalid));
+    }
+    tokens
+}
+
+/// Render tokens as text, showing undecodable bytes as `\xHH`
+#[must_use]
+pub fn render_tokens(tokens: &[ByteToken]) -> String {
+    let mut output = String::with_capacity(tokens.len());
+    for token in tokens {
+        match token {
+            ByteToken::Char(c) => output.push(*c),
+            ByteToken::Invalid(byte) => {
+                let _ = write!(output, "\\x{byte:02x}");
+            }
+        }
+    }
+    output
+}
+
+impl CharDiff {
+    /// Character-level diff of byte strings that may not be valid UTF-8
+    ///
+    /// Valid UTF-8 runs are compared as characters; undecodable bytes are
+    /// compared byte by byte and rendered as `\xHH`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kodegen_utils::char_diff::CharDiff;
+    ///
+    /// let diff = CharDiff::from_bytes(b"caf\xe9 au lait", "café au lait".as_bytes());
+    /// assert_eq!(diff.format(), "caf{-\\xe9-}{+é+} au lait");
+    /// ```
+    #[must_use]
+    pub fn from_bytes(expected: &[u8], actual: &[u8]) -> Self {
+        let a = decode_bytes(expected);
+        let b = decode_bytes(actual);
+
+        let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+        let suffix = a[prefix..]
+            .iter()
+            .rev()
+            .zip(b[prefix..].iter().rev())
+            .take_while(|(x, y)| x == y)
+            .count();
+
+        Self {
+            common_prefix: render_tokens(&a[..prefix]),
+            expected_part: render_tokens(&a[prefix..a.len() - suffix]),
+            actual_part: render_tokens(&b[prefix..b.len() - suffix]),
+            common_suffix: render_tokens(&a[a.len() - suffix..]),
+        }
+    }
+}
+
+/// Minimal edit script between byte strings that may not be valid UTF-8
+///
+/// Undecodable bytes appear as `\xHH` in the op texts, so the result is
+/// for display; use [`diff_chars`] when both inputs are valid UTF-8.
+#[must_use]
+pub fn diff_bytes(expected: &[u8], actual: &[u8]) -> Vec<DiffOp> {
+    let a = decode_bytes(expected);
+    let b = decode_bytes(actual);
+
+    let mut ops = Vec::new();
+    let (mut ai, mut bi) = (0, 0);
+    for (kind, len) in diff_slices(&a, &b) {
+        let text = match kind {
+            DiffKind::Equal | DiffKind::Delete => render_tokens(&a[ai..ai + len]),
+            DiffKind::Insert => render_tokens(&b[bi..bi + len]),
+        };
+        match kind {
+            DiffKind::Equal => {
+                ai += len;
+                bi += len;
+            }
+            DiffKind::Delete => ai += len,
+            DiffKind::Insert => bi += len,
+        }
+        ops.push(DiffOp::new(kind, text));
+    }
+    ops
+}
+
+// ============================================================================
+// STATISTICS
+// ============================================================================
+
+/// Change-size summary of a diff
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffStats {
+    /// Lines only present in the actual text
+    pub lines_added: usize,
+
+    /// Lines only present in the expected text
+    pub lines_removed: usize,
+
+    /// Lines changed in place (paired removed/added lines within a hunk)
+    pub lines_modified: usize,
+
+    /// Characters inserted, from a character diff of each hunk
+    pub chars_inserted: usize,
+
+    /// Characters deleted, from a character diff of each hunk
+    pub chars_deleted: usize,
+
+    /// Number of hunks
+    pub hunks: usize,
+}
+
+impl DiffStats {
+    /// Compute statistics for the change from `expected` to `actual`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kodegen_utils::char_diff::DiffStats;
+    ///
+    /// let stats = DiffStats::compute("a\nb\nc\n", "a\nB\nc\nd\n");
+    /// assert_eq!(stats.hunks, 2);
+    /// assert_eq!(stats.lines_modified, 1);
+    /// assert_eq!(stats.lines_added, 1);
+    /// assert_eq!(stats.lines_removed, 0);
+    /// ```
+    #[must_use]
+    pub fn compute(expected: &str, actual: &str) -> Self {
+        let mut stats = Self::default();
+        for hunk in diff_hunks(expected, actual) {
+            let old_count = hunk.old_lines.len();
+            let new_count = hunk.new_lines.len();
+            let modified = old_count.min(new_count);
+
+            stats.hunks += 1;
+            stats.lines_modified += modified;
+            stats.lines_removed += old_count - modified;
+            stats.lines_added += new_count - modified;
+
+            for op in diff_chars(&hunk.old_text, &hunk.new_text) {
+                match op.kind {
+                    DiffKind::Equal => {}
+                    DiffKind::Delete => stats.chars_deleted += char_len(&op.text),
+                    DiffKind::Insert => stats.chars_inserted += char_len(&op.text),
+                }
+            }
+        }
+        stats
+    }
+
+    /// Whether the diff contains no changes
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hunks == 0
+    }
+}
+
+impl std::ops::AddAssign for DiffStats {
+    fn add_assign(&mut self, other: Self) {
+        self.lines_added += other.lines_added;
+        self.lines_removed += other.lines_removed;
+        self.lines_modified += other.lines_modified;
+        self.chars_inserted += other.chars_inserted;
+        self.chars_deleted += other.chars_deleted;
+        self.hunks += other.hunks;
+    }
+}
+
+impl CharDiff {
+    /// Change-size statistics for this diff
+    #[must_use]
+    pub fn stats(&self) -> DiffStats {
+        DiffStats::compute(
+            &format!(
+                "{}{}{}",
+                self.common_prefix, self.expected_part, self.common_suffix
+            ),
+            &format!(
+                "{}{}{}",
+                self.common_prefix, self.actual_part, self.common_suffix
+            ),
+        )
+    }
+}
@@ -0,0 +1,245 @@
+//! Homoglyph and confusable character detection
+//!
+//! Uses the Unicode TR39 confusables data: two strings are confusable when
+//! their *skeletons* (every character replaced by its prototype) are equal.
+//! A Cyrillic `а` (U+0430) and a Latin `a` both have the skeleton `a`, so a
+//! search string containing one can still be located in a file containing
+//! the other, and the file's spelling used as the fixed search string.
+
+use crate::char_analysis::is_exotic_space;
+use crate::char_diff::{DiffKind, diff_chars};
+use crate::smart_punctuation::ascii_equivalent;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::skeleton as tr39_skeleton;
+
+/// A look-alike substitution between the expected and actual text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Confusable {
+    /// Text as it appears in the expected (search) string
+    pub expected: String,
+
+    /// Look-alike text as it appears in the actual (file) string
+    pub actual: String,
+}
+
+impl Confusable {
+    /// Describe the pair with code points, e.g. `'а' U+0430 vs 'a' U+0061`
+    #[must_use]
+    pub fn describe(&self) -> String {
+        format!(
+            "'{}' {} vs '{}' {}",
+            self.expected,
+            code_points(&self.expected),
+            self.actual,
+            code_points(&self.actual)
+        )
+    }
+}
+
+/// A region of a haystack whose skeleton matches a needle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfusableMatch {
+    /// Byte range in the haystack
+    pub range: Range<usize>,
+
+    /// The haystack's text for the range (the corrected search string)
+    pub text: String,
+}
+
+/// TR39 skeleton of `text`
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::confusables::skeleton;
+///
+/// assert_eq!(skeleton("p\u{0430}ypal"), skeleton("paypal"));
+/// ```
+#[must_use]
+pub fn skeleton(text: &str) -> String {
+    tr39_skeleton(text).collect()
+}
+
+/// Whether `a` and `b` differ but look the same (equal skeletons)
+#[must_use]
+pub fn is_confusable(a: &str, b: &str) -> bool {
+    a != b && skeleton(a) == skeleton(b)
+}
+
+/// List the look-alike substitutions that turn `expected` into `actual`
+///
+/// Each changed region of the character diff is checked; substitutions with
+/// matching skeletons are reported per character where possible, otherwise
+/// as a whole run (e.g. `rn` vs `m`). Pairs are deduplicated.
+///
+/// TR39 skeletons also equate canonically or compatibility-equivalent forms,
+/// typographic and ASCII punctuation, and exotic and ASCII spaces. Pairs
+/// those explain are left out: they have their own, more specific diagnoses.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::confusables::confusable_pairs;
+///
+/// let pairs = confusable_pairs("let v\u{0430}lue = 1;", "let value = 1;");
+/// assert_eq!(pairs.len(), 1);
+/// assert_eq!(pairs[0].expected, "\u{0430}");
+/// assert_eq!(pairs[0].actual, "a");
+/// assert!(confusable_pairs("caf\u{e9}", "cafe\u{301}").is_empty());
+/// ```
+#[must_use]
+pub fn confusable_pairs(expected: &str, actual: &str) -> Vec<Confusable> {
+    let ops = diff_chars(expected, actual);
+    let mut pairs: Vec<Confusable> = Vec::new();
+
+    let mut i = 0;
+    while i < ops.len() {
+        if ops[i].kind == DiffKind::Equal {
+            i += 1;
+            continue;
+        }
+
+        // Gather one changed region (deletes and inserts between equalities)
+        let mut deleted = String::new();
+        let mut inserted = String::new();
+        while i < ops.len() && ops[i].kind != DiffKind::Equal {
+            match ops[i].kind {
+                DiffKind::Delete => deleted.push_str(&ops[i].text),
+                DiffKind::Insert => inserted.push_str(&ops[i].text),
+                DiffKind::Equal => unreachable!(),
+            }
+            i += 1;
+        }
+
+        for pair in region_pairs(&deleted, &inserted) {
+            if !pairs.contains(&pair) && fold(&pair.expected) != fold(&pair.actual) {
+                pairs.push(pair);
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Find regions of `haystack` that are confusable with (or equal to) `needle`
+///
+/// Matches are non-overlapping and reported in order. A single match's
+/// `text` is the spelling that will match the file exactly.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::confusables::find_confusable;
+///
+/// let file = "fn main() {\n    let value = 1;\n}\n";
+/// let matches = find_confusable(file, "let v\u{0430}lue = 1;");
+/// assert_eq!(matches.len(), 1);
+/// assert_eq!(matches[0].text, "let value = 1;");
+/// ```
+#[must_use]
+pub fn find_confusable(haystack: &str, needle: &str) -> Vec<ConfusableMatch> {
+    let needle_skeleton: String = needle.chars().map(char_skeleton).collect();
+    if needle_skeleton.is_empty() {
+        return Vec::new();
+    }
+
+    // Skeleton of the haystack, built per character so offsets map back
+    let mut haystack_skeleton = String::new();
+    let mut starts: Vec<(usize, usize)> = Vec::new(); // (skeleton offset, haystack offset)
+    for (offset, c) in haystack.char_indices() {
+        starts.push((haystack_skeleton.len(), offset));
+        haystack_skeleton.push_str(&char_skeleton(c));
+    }
+    starts.push((haystack_skeleton.len(), haystack.len()));
+
+    let mut matches = Vec::new();
+    let mut index = 0;
+    while index + 1 < starts.len() {
+        let (skeleton_start, byte_start) = starts[index];
+        if haystack_skeleton[skeleton_start..].starts_with(&needle_skeleton) {
+            let skeleton_end = skeleton_start + needle_skeleton.len();
+            // The match must end on a character boundary of the haystack.
+            // Skeleton offsets are sorted, but characters with an empty
+            // skeleton repeat one, so take the first entry at the offset
+            let end_index = starts.partition_point(|&(s, _)| s < skeleton_end);
+            if starts
+                .get(end_index)
+                .is_some_and(|&(s, _)| s == skeleton_end)
+            {
+                let byte_end = starts[end_index].1;
+                matches.push(ConfusableMatch {
+                    range: byte_start..byte_end,
+                    text: haystack[byte_start..byte_end].to_string(),
+                });
+                index = end_index;
+                continue;
+            }
+        }
+        index += 1;
+    }
+
+    matches
+}
+
+/// Confusable pairs within one changed region
+fn region_pairs(deleted: &str, inserted: &str) -> Vec<Confusable> {
+    if deleted.is_empty() || inserted.is_empty() || skeleton(deleted) != skeleton(inserted) {
+        return Vec::new();
+    }
+
+    let deleted_chars: Vec<char> = deleted.chars().collect();
+    let inserted_chars: Vec<char> = inserted.chars().collect();
+    let per_char = deleted_chars.len() == inserted_chars.len()
+        && deleted_chars
+            .iter()
+            .zip(&inserted_chars)
+            .all(|(&d, &i)| char_skeleton(d) == char_skeleton(i));
+
+    if per_char {
+        deleted_chars
+            .iter()
+            .zip(&inserted_chars)
+            .filter(|(d, i)| d != i)
+            .map(|(d, i)| Confusable {
+                expected: d.to_string(),
+                actual: i.to_string(),
+            })
+            .collect()
+    } else {
+        vec![Confusable {
+            expected: deleted.to_string(),
+            actual: inserted.to_string(),
+        }]
+    }
+}
+
+/// NFKC with typographic punctuation and exotic spaces mapped to ASCII
+fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.nfkc() {
+        if is_exotic_space(u32::from(c)) {
+            folded.push(' ');
+        } else if let Some(ascii) = ascii_equivalent(c) {
+            folded.push_str(ascii);
+        } else {
+            folded.push(c);
+        }
+    }
+    folded
+}
+
+fn char_skeleton(c: char) -> String {
+    let mut buffer = [0; 4];
+    skeleton(c.encode_utf8(&mut buffer))
+}
+
+/// Format code points as `U+0430` (space-separated for runs)
+fn code_points(text: &str) -> String {
+    text.chars()
+        .map(|c| format!("U+{:04X}", u32::from(c)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,6 +1,8 @@
+use crate::char_diff::DiffStats;
 use chrono::{DateTime, Utc};
 use kodegen_config::KodegenConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::OnceLock;
@@ -11,7 +13,23 @@ use tokio::sync::mpsc;
 // LOG ENTRY TYPE
 // ============================================================================
 
+/// Column names of the TSV log, in row order
+const TSV_HEADER: &str = "timestamp\tsearch_text\tfound_text\tsimilarity\texecution_time_ms\t\
+     exact_match_count\texpected_replacements\tfuzzy_threshold\t\
+     below_threshold\tdiff\tsearch_length\tfound_length\t\
+     file_extension\tcharacter_codes\tunique_character_count\t\
+     diff_length\tresult\tlines_added\tlines_removed\t\
+     lines_modified\tchars_inserted\tchars_deleted\thunk_count";
+
+/// Columns in log files written before the diff-stats columns were added
+const LEGACY_TSV_COLUMNS: usize = 17;
+
+/// One `edit_block` attempt
+///
+/// Build entries with [`EditBlockLogEntry::new`] and set the remaining
+/// fields directly; new fields may be added in future releases.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct EditBlockLogEntry {
     pub timestamp: DateTime<Utc>,
     pub search_text: String,
@@ -30,6 +48,9 @@ pub struct EditBlockLogEntry {
     pub unique_character_count: Option<usize>,
     pub diff_length: Option<usize>,
     pub result: EditBlockResult,
+    /// Line/character change counts, when the edit produced a diff
+    #[serde(default)]
+    pub diff_stats: Option<DiffStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,9 +63,73 @@ pub enum EditBlockResult {
 }
 
 impl EditBlockLogEntry {
+    /// Entry for a search in a file with the given extension, timestamped now
+    ///
+    /// Match details start empty; record the text found with
+    /// [`Self::with_found`].
+    #[must_use]
+    pub fn new(
+        search_text: impl Into<String>,
+        file_extension: impl Into<String>,
+        result: EditBlockResult,
+    ) -> Self {
+        let search_text = search_text.into();
+        Self {
+            timestamp: Utc::now(),
+            search_length: search_text.chars().count(),
+            search_text,
+            found_text: None,
+            similarity: None,
+            execution_time_ms: 0.0,
+            exact_match_count: 0,
+            expected_replacements: 1,
+            fuzzy_threshold: 0.0,
+            below_threshold: false,
+            diff: None,
+            found_length: None,
+            file_extension: file_extension.into(),
+            character_codes: None,
+            unique_character_count: None,
+            diff_length: None,
+            result,
+            diff_stats: None,
+        }
+    }
+
+    /// Record the text found in the file and its similarity to the search
+    ///
+    /// Also fills in the found length and the diff statistics between the
+    /// search and found text.
+    #[must_use]
+    pub fn with_found(mut self, found_text: impl Into<String>, similarity: f64) -> Self {
+        let found_text = found_text.into();
+        self.found_length = Some(found_text.chars().count());
+        self.diff_stats = Some(DiffStats::compute(&self.search_text, &found_text));
+        self.found_text = Some(found_text);
+        self.similarity = Some(similarity);
+        self
+    }
+
     /// Format as TSV line (tab-separated values)
     #[must_use]
     pub fn to_tsv(&self) -> String {
+        self.tsv_fields().join("\t")
+    }
+
+    /// Format as a TSV line matching an existing log file's header
+    ///
+    /// Files created before the diff-stats columns existed keep their
+    /// original layout, so the stats are left out of their rows.
+    #[must_use]
+    pub fn to_tsv_for_header(&self, header: &str) -> String {
+        let mut fields = self.tsv_fields();
+        if header.trim_end().split('\t').count() <= LEGACY_TSV_COLUMNS {
+            fields.truncate(LEGACY_TSV_COLUMNS);
+        }
+        fields.join("\t")
+    }
+
+    fn tsv_fields(&self) -> Vec<String> {
         vec![
             self.timestamp.to_rfc3339(),
             escape_tsv(&self.search_text),
@@ -63,8 +148,14 @@ impl EditBlockLogEntry {
             format_option(self.unique_character_count),
             format_option(self.diff_length),
             format!("{:?}", self.result),
+            // Stats columns go last so rows stay aligned with older headers
+            format_option(self.diff_stats.map(|s| s.lines_added)),
+            format_option(self.diff_stats.map(|s| s.lines_removed)),
+            format_option(self.diff_stats.map(|s| s.lines_modified)),
+            format_option(self.diff_stats.map(|s| s.chars_inserted)),
+            format_option(self.diff_stats.map(|s| s.chars_deleted)),
+            format_option(self.diff_stats.map(|s| s.hunks)),
         ]
-        .join("\t")
     }
 }
 
@@ -80,6 +171,82 @@ fn format_option<T: ToString>(opt: Option<T>) -> String {
     opt.map(|v| v.to_string()).unwrap_or_default()
 }
 
+// ============================================================================
+// PER-EXTENSION AGGREGATION
+// ============================================================================
+
+/// Accumulated diff statistics for one file extension
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionDiffStats {
+    /// Number of logged edits that carried diff stats
+    pub edits: usize,
+
+    /// Sum of the stats of those edits
+    pub totals: DiffStats,
+}
+
+/// Diff statistics aggregated by file extension
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffStatsByExtension {
+    extensions: BTreeMap<String, ExtensionDiffStats>,
+}
+
+impl DiffStatsByExtension {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an entry's stats to its extension (entries without stats are ignored)
+    pub fn record(&mut self, entry: &EditBlockLogEntry) {
+        if let Some(stats) = entry.diff_stats {
+            self.record_stats(&entry.file_extension, stats);
+        }
+    }
+
+    /// Add stats for an extension directly
+    pub fn record_stats(&mut self, extension: &str, stats: DiffStats) {
+        let bucket = self.extensions.entry(extension.to_string()).or_default();
+        bucket.edits += 1;
+        bucket.totals += stats;
+    }
+
+    /// Stats for one extension
+    #[must_use]
+    pub fn get(&self, extension: &str) -> Option<&ExtensionDiffStats> {
+        self.extensions.get(extension)
+    }
+
+    /// All extensions, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ExtensionDiffStats)> {
+        self.extensions
+            .iter()
+            .map(|(ext, stats)| (ext.as_str(), stats))
+    }
+
+    /// Totals across every extension
+    #[must_use]
+    pub fn overall(&self) -> ExtensionDiffStats {
+        self.extensions
+            .values()
+            .fold(ExtensionDiffStats::default(), |mut acc, bucket| {
+                acc.edits += bucket.edits;
+                acc.totals += bucket.totals;
+                acc
+            })
+    }
+}
+
+impl<'a> FromIterator<&'a EditBlockLogEntry> for DiffStatsByExtension {
+    fn from_iter<I: IntoIterator<Item = &'a EditBlockLogEntry>>(entries: I) -> Self {
+        let mut aggregate = Self::new();
+        for entry in entries {
+            aggregate.record(entry);
+        }
+        aggregate
+    }
+}
+
 // ============================================================================
 // ASYNC BACKGROUND LOGGER (FIRE-AND-FORGET)
 // ============================================================================
@@ -137,8 +304,9 @@ impl EditBlockLogger {
             let mut flush_interval = tokio::time::interval(std::time::Duration::from_secs(5));
             flush_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
-            // Lazy file writer initialization
+            // Lazy file writer initialization, with the header rows must match
             let mut writer: Option<tokio::io::BufWriter<tokio::fs::File>> = None;
+            let mut header = String::from(TSV_HEADER);
 
             loop {
                 tokio::select! {
@@ -153,7 +321,10 @@ impl EditBlockLogger {
                             // Ensure writer is initialized
                             if writer.is_none() {
                                 match Self::init_log_file(&log_path).await {
-                                    Ok(w) => writer = Some(w),
+                                    Ok((w, existing)) => {
+                                        writer = Some(w);
+                                        header = existing;
+                                    }
                                     Err(e) => {
                                         log::error!("Failed to initialize edit_block log: {e}");
                                         pending_entries.clear();
@@ -165,7 +336,7 @@ impl EditBlockLogger {
                             // Write all pending entries
                             if let Some(ref mut w) = writer {
                                 for entry in pending_entries.drain(..) {
-                                    let line = format!("{}\n", entry.to_tsv());
+                                    let line = format!("{}\n", entry.to_tsv_for_header(&header));
                                     if let Err(e) = w.write_all(line.as_bytes()).await {
                                         log::error!("Failed to write edit_block log entry: {e}");
                                     }
@@ -182,13 +353,17 @@ impl EditBlockLogger {
                     // Channel closed (server shutdown)
                     else => {
                         // Final flush before exit
-                        if !pending_entries.is_empty() && writer.is_none() {
-                            writer = Self::init_log_file(&log_path).await.ok();
+                        if !pending_entries.is_empty()
+                            && writer.is_none()
+                            && let Ok((w, existing)) = Self::init_log_file(&log_path).await
+                        {
+                            writer = Some(w);
+                            header = existing;
                         }
 
                         if let Some(ref mut w) = writer {
                             for entry in pending_entries.drain(..) {
-                                let line = format!("{}\n", entry.to_tsv());
+                                let line = format!("{}\n", entry.to_tsv_for_header(&header));
                                 let _ = w.write_all(line.as_bytes()).await;
                             }
                             let _ = w.flush().await;
@@ -201,16 +376,24 @@ impl EditBlockLogger {
     }
 
     /// Initialize log file with headers (called from background task)
+    ///
+    /// Returns the writer and the file's header, which is the existing one
+    /// when appending to an older log.
     async fn init_log_file(
         log_path: &PathBuf,
-    ) -> std::io::Result<tokio::io::BufWriter<tokio::fs::File>> {
+    ) -> std::io::Result<(tokio::io::BufWriter<tokio::fs::File>, String)> {
         // Create directory
         if let Some(parent) = log_path.parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
 
-        // Check if file exists
+        // Check if file exists, and read its header
         let file_exists = tokio::fs::try_exists(log_path).await.unwrap_or(false);
+        let existing_header = if file_exists {
+            Self::read_header(log_path).await
+        } else {
+            None
+        };
 
         // Open file in append mode
         let file = tokio::fs::OpenOptions::new()
@@ -223,16 +406,29 @@ impl EditBlockLogger {
 
         // Write headers if new file
         if !file_exists {
-            let header = "timestamp\tsearch_text\tfound_text\tsimilarity\texecution_time_ms\t\
-                 exact_match_count\texpected_replacements\tfuzzy_threshold\t\
-                 below_threshold\tdiff\tsearch_length\tfound_length\t\
-                 file_extension\tcharacter_codes\tunique_character_count\t\
-                 diff_length\tresult\n";
-            writer.write_all(header.as_bytes()).await?;
+            writer
+                .write_all(format!("{TSV_HEADER}\n").as_bytes())
+                .await?;
             writer.flush().await?;
         }
 
-        Ok(writer)
+        Ok((
+            writer,
+            existing_header.unwrap_or_else(|| TSV_HEADER.to_string()),
+        ))
+    }
+
+    /// First line of an existing log file
+    async fn read_header(log_path: &PathBuf) -> Option<String> {
+        use tokio::io::AsyncBufReadExt;
+
+        let file = tokio::fs::File::open(log_path).await.ok()?;
+        let mut header = String::new();
+        tokio::io::BufReader::new(file)
+            .read_line(&mut header)
+            .await
+            .ok()?;
+        (!header.is_empty()).then_some(header)
     }
 }
 
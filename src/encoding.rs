@@ -0,0 +1,461 @@
+//! Text encoding detection and transcoding
+//!
+//! [`EncodingIssue::ReplacementChar`](crate::char_analysis::EncodingIssue)
+//! only shows that a file was already decoded wrongly. This module works on
+//! the raw bytes instead: it recognises BOM-tagged UTF-8/16/32, guesses
+//! BOM-less UTF-16 and legacy encodings (Latin-1, Windows-1252, Shift_JIS,
+//! GBK), decodes to a `String`, and re-encodes edited text to the original
+//! encoding and BOM so legacy files round-trip without mojibake.
+
+use crate::bom::detect_bom;
+use encoding_rs::{Encoding, GBK, SHIFT_JIS, WINDOWS_1252};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Minimum share of ASCII-range code units with a zero partner byte for
+/// BOM-less UTF-16 to be assumed
+const UTF16_ZERO_RATIO: f64 = 0.3;
+
+/// Minimum plausibility score for a Shift_JIS/GBK decoding to be accepted
+const CJK_MIN_SCORE: f64 = 0.8;
+
+// ============================================================================
+// ENCODINGS
+// ============================================================================
+
+/// A text encoding this module can detect, decode and encode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+    /// ISO-8859-1: every byte maps to U+0000-U+00FF
+    Latin1,
+    Windows1252,
+    ShiftJis,
+    Gbk,
+}
+
+impl TextEncoding {
+    /// Conventional name, e.g. "UTF-16LE", "Shift_JIS"
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Utf32Le => "UTF-32LE",
+            Self::Utf32Be => "UTF-32BE",
+            Self::Latin1 => "ISO-8859-1",
+            Self::Windows1252 => "windows-1252",
+            Self::ShiftJis => "Shift_JIS",
+            Self::Gbk => "GBK",
+        }
+    }
+
+    /// Byte order mark, or an empty slice for encodings without one
+    #[must_use]
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => b"\xEF\xBB\xBF",
+            Self::Utf16Le => b"\xFF\xFE",
+            Self::Utf16Be => b"\xFE\xFF",
+            Self::Utf32Le => b"\xFF\xFE\x00\x00",
+            Self::Utf32Be => b"\x00\x00\xFE\xFF",
+            Self::Latin1 | Self::Windows1252 | Self::ShiftJis | Self::Gbk => b"",
+        }
+    }
+
+    /// Decode `bytes` (without BOM); malformed input becomes U+FFFD
+    ///
+    /// Returns the text and whether any replacement happened.
+    #[must_use]
+    pub fn decode(&self, bytes: &[u8]) -> (String, bool) {
+        match self {
+            Self::Utf8 => {
+                let text = String::from_utf8_lossy(bytes);
+                let had_errors = matches!(text, std::borrow::Cow::Owned(_));
+                (text.into_owned(), had_errors)
+            }
+            Self::Utf16Le | Self::Utf16Be => {
+                let big_endian = *self == Self::Utf16Be;
+                let units = bytes.as_chunks::<2>().0.iter().map(|&pair| {
+                    if big_endian {
+                        u16::from_be_bytes(pair)
+                    } else {
+                        u16::from_le_bytes(pair)
+                    }
+                });
+                let mut had_errors = bytes.len() % 2 != 0;
+                let mut text: String = char::decode_utf16(units)
+                    .map(|c| {
+                        c.unwrap_or_else(|_| {
+                            had_errors = true;
+                            char::REPLACEMENT_CHARACTER
+                        })
+                    })
+                    .collect();
+                if bytes.len() % 2 != 0 {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                }
+                (text, had_errors)
+            }
+            Self::Utf32Le | Self::Utf32Be => {
+                let big_endian = *self == Self::Utf32Be;
+                let mut had_errors = bytes.len() % 4 != 0;
+                let mut text: String = bytes
+                    .as_chunks::<4>()
+                    .0
+                    .iter()
+                    .map(|&quad| {
+                        let code = if big_endian {
+                            u32::from_be_bytes(quad)
+                        } else {
+                            u32::from_le_bytes(quad)
+                        };
+                        char::from_u32(code).unwrap_or_else(|| {
+                            had_errors = true;
+                            char::REPLACEMENT_CHARACTER
+                        })
+                    })
+                    .collect();
+                if bytes.len() % 4 != 0 {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                }
+                (text, had_errors)
+            }
+            Self::Latin1 => (bytes.iter().map(|&b| char::from(b)).collect(), false),
+            Self::Windows1252 => decode_legacy(WINDOWS_1252, bytes),
+            Self::ShiftJis => decode_legacy(SHIFT_JIS, bytes),
+            Self::Gbk => decode_legacy(GBK, bytes),
+        }
+    }
+
+    /// Encode `text` (without BOM)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EncodingError::Unmappable`] for the first character the
+    /// encoding cannot represent.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, EncodingError> {
+        let unmappable = |character| EncodingError::Unmappable {
+            character,
+            encoding: *self,
+        };
+        match self {
+            Self::Utf8 => Ok(text.as_bytes().to_vec()),
+            Self::Utf16Le => Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Self::Utf16Be => Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+            Self::Utf32Le => Ok(text
+                .chars()
+                .flat_map(|c| u32::from(c).to_le_bytes())
+                .collect()),
+            Self::Utf32Be => Ok(text
+                .chars()
+                .flat_map(|c| u32::from(c).to_be_bytes())
+                .collect()),
+            Self::Latin1 => text
+                .chars()
+                .map(|c| u8::try_from(u32::from(c)).map_err(|_| unmappable(c)))
+                .collect(),
+            Self::Windows1252 => encode_legacy(WINDOWS_1252, text).map_err(unmappable),
+            Self::ShiftJis => encode_legacy(SHIFT_JIS, text).map_err(unmappable),
+            Self::Gbk => encode_legacy(GBK, text).map_err(unmappable),
+        }
+    }
+}
+
+fn decode_legacy(codec: &'static Encoding, bytes: &[u8]) -> (String, bool) {
+    let (text, had_errors) = codec.decode_without_bom_handling(bytes);
+    (text.into_owned(), had_errors)
+}
+
+/// Encode with `encoding_rs`, returning the first unmappable character on failure
+fn encode_legacy(codec: &'static Encoding, text: &str) -> Result<Vec<u8>, char> {
+    let (bytes, _, had_errors) = codec.encode(text);
+    if !had_errors {
+        return Ok(bytes.into_owned());
+    }
+    // encoding_rs substitutes numeric character references; find the culprit
+    let mut buffer = [0; 4];
+    Err(text
+        .chars()
+        .find(|c| codec.encode(c.encode_utf8(&mut buffer)).2)
+        .unwrap_or(char::REPLACEMENT_CHARACTER))
+}
+
+impl std::fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Errors re-encoding text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingError {
+    /// The encoding has no representation for `character`
+    Unmappable {
+        character: char,
+        encoding: TextEncoding,
+    },
+}
+
+impl std::fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unmappable {
+                character,
+                encoding,
+            } => write!(
+                f,
+                "'{character}' (U+{:04X}) cannot be encoded in {encoding}",
+                u32::from(*character)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+// ============================================================================
+// DETECTION
+// ============================================================================
+
+/// Result of guessing a byte sequence's encoding
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct EncodingDetection {
+    pub encoding: TextEncoding,
+
+    /// Whether the bytes start with the encoding's BOM
+    pub has_bom: bool,
+
+    /// 1.0 for BOMs and valid UTF-8; lower for heuristic guesses
+    pub confidence: f64,
+}
+
+/// Guess the encoding of `bytes`
+///
+/// In order: a byte order mark; valid UTF-8 (including plain ASCII);
+/// BOM-less UTF-16 (zero bytes in alternate positions); Shift_JIS or GBK
+/// when the bytes decode cleanly into clustered CJK text; otherwise
+/// Windows-1252 if any 0x80-0x9F byte is present, else Latin-1.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::encoding::{detect_encoding, TextEncoding};
+///
+/// assert_eq!(detect_encoding(b"\xFF\xFEh\x00i\x00").encoding, TextEncoding::Utf16Le);
+/// assert_eq!(detect_encoding("café".as_bytes()).encoding, TextEncoding::Utf8);
+/// assert_eq!(detect_encoding(b"caf\xE9").encoding, TextEncoding::Latin1);
+/// assert_eq!(detect_encoding(b"\x93quoted\x94").encoding, TextEncoding::Windows1252);
+/// ```
+#[must_use]
+pub fn detect_encoding(bytes: &[u8]) -> EncodingDetection {
+    let detected = |encoding, has_bom, confidence| EncodingDetection {
+        encoding,
+        has_bom,
+        confidence,
+    };
+
+    if let Some(bom) = detect_bom(bytes) {
+        return detected(bom.encoding(), true, 1.0);
+    }
+
+    // ASCII-only UTF-16 is also valid UTF-8, so the zero-byte check runs first
+    if let Some(encoding) = guess_utf16(bytes) {
+        return detected(encoding, false, 0.8);
+    }
+
+    if std::str::from_utf8(bytes).is_ok() {
+        return detected(TextEncoding::Utf8, false, 1.0);
+    }
+
+    if let Some(encoding) = guess_cjk(bytes) {
+        return detected(encoding, false, 0.7);
+    }
+
+    if bytes.iter().any(|b| (0x80..=0x9F).contains(b)) {
+        detected(TextEncoding::Windows1252, false, 0.5)
+    } else {
+        detected(TextEncoding::Latin1, false, 0.5)
+    }
+}
+
+/// BOM-less UTF-16: ASCII text leaves a zero in every other byte
+fn guess_utf16(bytes: &[u8]) -> Option<TextEncoding> {
+    if bytes.len() < 2 || bytes.len() % 2 != 0 {
+        return None;
+    }
+    let pairs = bytes.len() / 2;
+    let ratio = |zero_index: usize| {
+        let count = bytes
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .filter(|pair| pair[zero_index] == 0 && pair[1 - zero_index] != 0)
+            .count();
+        count as f64 / pairs as f64
+    };
+    let (le, be) = (ratio(1), ratio(0));
+    if le >= UTF16_ZERO_RATIO && le > be {
+        Some(TextEncoding::Utf16Le)
+    } else if be >= UTF16_ZERO_RATIO {
+        Some(TextEncoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Shift_JIS or GBK, if either decodes cleanly into plausible CJK text
+///
+/// Kana only occur in Japanese, so a Shift_JIS decoding containing kana
+/// wins over GBK.
+fn guess_cjk(bytes: &[u8]) -> Option<TextEncoding> {
+    let decode = |codec: &'static Encoding| {
+        codec
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .map(|text| text.into_owned())
+    };
+
+    let sjis = decode(SHIFT_JIS).map(|text| (cjk_score(&text), text.chars().any(is_kana)));
+    let gbk = decode(GBK).map(|text| cjk_score(&text));
+
+    match (sjis, gbk) {
+        (Some((score, true)), _) if score >= CJK_MIN_SCORE => Some(TextEncoding::ShiftJis),
+        (_, Some(score)) if score >= CJK_MIN_SCORE => Some(TextEncoding::Gbk),
+        (Some((score, _)), _) if score >= CJK_MIN_SCORE => Some(TextEncoding::ShiftJis),
+        _ => None,
+    }
+}
+
+/// Share of non-ASCII characters that look like real CJK text
+///
+/// A CJK character wedged between two ASCII letters is how Latin-1 text
+/// like `na\xEFve` misdecodes, so those do not count.
+fn cjk_score(text: &str) -> f64 {
+    let chars: Vec<char> = text.chars().collect();
+    let mut non_ascii = 0;
+    let mut plausible = 0;
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii() {
+            continue;
+        }
+        non_ascii += 1;
+        let letter_at = |index: Option<usize>| {
+            index
+                .and_then(|index| chars.get(index))
+                .is_some_and(char::is_ascii_alphabetic)
+        };
+        let wedged = letter_at(i.checked_sub(1)) && letter_at(Some(i + 1));
+        if is_cjk(c) && !wedged {
+            plausible += 1;
+        }
+    }
+    if non_ascii == 0 {
+        0.0
+    } else {
+        f64::from(plausible) / f64::from(non_ascii)
+    }
+}
+
+/// Full-width hiragana and katakana
+///
+/// Half-width katakana are left out: Shift_JIS maps most single high bytes
+/// to them, so they are what GBK and Latin-1 text misdecodes as.
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30FF}')
+}
+
+fn is_cjk(c: char) -> bool {
+    is_kana(c)
+        || matches!(
+            c,
+            '\u{3000}'..='\u{303F}' // CJK punctuation
+                | '\u{4E00}'..='\u{9FFF}' // Unified ideographs
+                | '\u{3400}'..='\u{4DBF}' // Extension A
+                | '\u{FF00}'..='\u{FF65}' // Fullwidth forms
+                | '\u{FFE0}'..='\u{FFEF}'
+        )
+}
+
+// ============================================================================
+// ROUND-TRIP DECODING
+// ============================================================================
+
+/// Decoded text plus what is needed to write it back unchanged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DecodedText {
+    /// Decoded content, without BOM
+    pub text: String,
+
+    pub encoding: TextEncoding,
+
+    /// Whether the source started with a BOM (restored by [`Self::encode`])
+    pub has_bom: bool,
+
+    /// Detection confidence (1.0 when the encoding was given)
+    pub confidence: f64,
+
+    /// Whether malformed input was replaced with U+FFFD (lossy)
+    pub had_errors: bool,
+}
+
+impl DecodedText {
+    /// Encode `text` back to the source encoding, restoring the BOM
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EncodingError::Unmappable`] if `text` contains a character
+    /// the source encoding cannot represent.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, EncodingError> {
+        let mut bytes = Vec::with_capacity(text.len() + 4);
+        if self.has_bom {
+            bytes.extend_from_slice(self.encoding.bom());
+        }
+        bytes.extend(self.encoding.encode(text)?);
+        Ok(bytes)
+    }
+}
+
+/// Detect the encoding of `bytes` and decode them
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::encoding::{decode, TextEncoding};
+///
+/// let source = b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd = 1;"; // "こんにちは = 1;" in Shift_JIS
+/// let decoded = decode(source);
+/// assert_eq!(decoded.encoding, TextEncoding::ShiftJis);
+/// assert_eq!(decoded.text, "こんにちは = 1;");
+///
+/// let edited = decoded.text.replace("1", "2");
+/// assert_eq!(decoded.encode(&edited).unwrap(), b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd = 2;");
+/// ```
+#[must_use]
+pub fn decode(bytes: &[u8]) -> DecodedText {
+    let detection = detect_encoding(bytes);
+    let mut decoded = decode_as(bytes, detection.encoding);
+    decoded.confidence = detection.confidence;
+    decoded
+}
+
+/// Decode `bytes` as `encoding`, stripping its BOM if present
+#[must_use]
+pub fn decode_as(bytes: &[u8], encoding: TextEncoding) -> DecodedText {
+    let bom = encoding.bom();
+    let has_bom = !bom.is_empty() && bytes.starts_with(bom);
+    let body = if has_bom { &bytes[bom.len()..] } else { bytes };
+    let (text, had_errors) = encoding.decode(body);
+    DecodedText {
+        text,
+        encoding,
+        has_bom,
+        confidence: 1.0,
+        had_errors,
+    }
+}
@@ -0,0 +1,417 @@
+//! Escaping-mismatch detection
+//!
+//! A common agent failure is a search string escaped one level too many
+//! (or too few): a literal `\n` where the file has a newline, `\"` where it
+//! has `"`, `&lt;` where it has `<`, `%20` where it has a space. This module
+//! unescapes and escapes text under several schemes and reports which
+//! transformation, applied how many times, turns the search string into
+//! text that is actually in the file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Deepest escaping level tried (double-escaped JSON is common, triple is not)
+const MAX_LEVELS: usize = 3;
+
+// ============================================================================
+// SCHEMES
+// ============================================================================
+
+/// An escaping convention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscapeScheme {
+    /// JSON string escapes: `\n`, `\"`, `\\`, `\uXXXX`
+    Json,
+    /// Rust/C string escapes: `\n`, `\t`, `\0`, `\x41`, `\u{41}`
+    CStyle,
+    /// HTML/XML entities: `&lt;`, `&amp;`, `&#39;`, `&#x27;`
+    HtmlEntities,
+    /// URL percent-encoding: `%20`, `%22`
+    Url,
+}
+
+impl EscapeScheme {
+    /// Every scheme, in detection order
+    pub const ALL: [Self; 4] = [Self::Json, Self::CStyle, Self::HtmlEntities, Self::Url];
+
+    /// Short label used in messages
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::CStyle => "Rust/C string",
+            Self::HtmlEntities => "HTML entity",
+            Self::Url => "URL",
+        }
+    }
+
+    /// Decode one level of escaping
+    ///
+    /// Returns `None` when `text` contains no escapes of this scheme, or a
+    /// malformed one (so ordinary text is never "unescaped" by accident).
+    #[must_use]
+    pub fn unescape(&self, text: &str) -> Option<String> {
+        match self {
+            Self::Json => unescape_backslashes(text, json_escape),
+            Self::CStyle => unescape_backslashes(text, c_escape),
+            Self::HtmlEntities => unescape_html(text),
+            Self::Url => unescape_url(text),
+        }
+    }
+
+    /// Encode one level of escaping
+    ///
+    /// Only characters that must be escaped are touched; the result is what
+    /// a serializer would typically emit.
+    #[must_use]
+    pub fn escape(&self, text: &str) -> String {
+        match self {
+            Self::Json => {
+                let quoted = serde_json::Value::from(text).to_string();
+                quoted[1..quoted.len() - 1].to_string()
+            }
+            Self::CStyle => {
+                let mut output = String::with_capacity(text.len());
+                for c in text.chars() {
+                    match c {
+                        '\\' => output.push_str("\\\\"),
+                        '"' => output.push_str("\\\""),
+                        '\n' => output.push_str("\\n"),
+                        '\r' => output.push_str("\\r"),
+                        '\t' => output.push_str("\\t"),
+                        '\0' => output.push_str("\\0"),
+                        _ => output.push(c),
+                    }
+                }
+                output
+            }
+            Self::HtmlEntities => {
+                let mut output = String::with_capacity(text.len());
+                for c in text.chars() {
+                    match c {
+                        '&' => output.push_str("&amp;"),
+                        '<' => output.push_str("&lt;"),
+                        '>' => output.push_str("&gt;"),
+                        '"' => output.push_str("&quot;"),
+                        '\'' => output.push_str("&#39;"),
+                        _ => output.push(c),
+                    }
+                }
+                output
+            }
+            Self::Url => {
+                let mut output = String::with_capacity(text.len());
+                for byte in text.bytes() {
+                    if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+                        output.push(char::from(byte));
+                    } else {
+                        output.push_str(&format!("%{byte:02X}"));
+                    }
+                }
+                output
+            }
+        }
+    }
+}
+
+// ============================================================================
+// DETECTION
+// ============================================================================
+
+/// Which way the search string has to be transformed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscapeDirection {
+    /// Search string is over-escaped (literal `\n` where the file has a newline)
+    Unescape,
+    /// Search string is under-escaped (newline where the file has a literal `\n`)
+    Escape,
+}
+
+/// An escaping difference between a search string and the file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct EscapeMismatch {
+    pub scheme: EscapeScheme,
+
+    pub direction: EscapeDirection,
+
+    /// How many times the transformation is applied (2 = double-escaped)
+    pub levels: usize,
+
+    /// The search string after the transformation
+    pub candidate: String,
+}
+
+impl EscapeMismatch {
+    /// Describe the fix, e.g. "search string is JSON-escaped twice; unescape it"
+    #[must_use]
+    pub fn description(&self) -> String {
+        let times = match self.levels {
+            1 => String::new(),
+            2 => " twice".to_string(),
+            n => format!(" {n} times"),
+        };
+        match self.direction {
+            EscapeDirection::Unescape => format!(
+                "search string is {}-escaped{times} but the file is not; unescape it",
+                self.scheme.label()
+            ),
+            EscapeDirection::Escape => format!(
+                "file is {}-escaped{times} but the search string is not; escape it",
+                self.scheme.label()
+            ),
+        }
+    }
+}
+
+/// Explain a mismatch between `expected` and `actual` as an escaping difference
+///
+/// Returns the first scheme (in [`EscapeScheme::ALL`] order) and the fewest
+/// levels for which transforming `expected` yields exactly `actual`.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::escaping::{diagnose_escaping, EscapeDirection, EscapeScheme};
+///
+/// let mismatch = diagnose_escaping("let s = \\\"hi\\\";\\n", "let s = \"hi\";\n").unwrap();
+/// assert_eq!(mismatch.scheme, EscapeScheme::Json);
+/// assert_eq!(mismatch.direction, EscapeDirection::Unescape);
+/// assert_eq!(mismatch.levels, 1);
+/// ```
+#[must_use]
+pub fn diagnose_escaping(expected: &str, actual: &str) -> Option<EscapeMismatch> {
+    if expected == actual {
+        return None;
+    }
+    candidates(expected).find(|mismatch| mismatch.candidate == actual)
+}
+
+/// Find an escaping transformation of `search` that occurs exactly once in `content`
+///
+/// Returns `None` if `search` already occurs in `content`, or if no
+/// transformation produces a unique match.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::escaping::{detect_escape_mismatch, EscapeScheme};
+///
+/// let file = "if a < b && c > d {\n    run();\n}\n";
+/// let mismatch = detect_escape_mismatch("a &lt; b &amp;&amp; c &gt; d", file).unwrap();
+/// assert_eq!(mismatch.scheme, EscapeScheme::HtmlEntities);
+/// assert_eq!(mismatch.candidate, "a < b && c > d");
+/// ```
+#[must_use]
+pub fn detect_escape_mismatch(search: &str, content: &str) -> Option<EscapeMismatch> {
+    if search.is_empty() || content.contains(search) {
+        return None;
+    }
+    candidates(search).find(|mismatch| {
+        let mut matches = content.match_indices(mismatch.candidate.as_str());
+        !mismatch.candidate.is_empty() && matches.next().is_some() && matches.next().is_none()
+    })
+}
+
+/// Every transformation of `text`: unescaping first, then escaping
+fn candidates(text: &str) -> impl Iterator<Item = EscapeMismatch> + '_ {
+    let unescaped = EscapeScheme::ALL.into_iter().flat_map(move |scheme| {
+        let mut levels = Vec::new();
+        let mut current = text.to_string();
+        while levels.len() < MAX_LEVELS
+            && let Some(next) = scheme.unescape(&current)
+        {
+            current = next;
+            levels.push(EscapeMismatch {
+                scheme,
+                direction: EscapeDirection::Unescape,
+                levels: levels.len() + 1,
+                candidate: current.clone(),
+            });
+        }
+        levels
+    });
+
+    let escaped = EscapeScheme::ALL.into_iter().flat_map(move |scheme| {
+        let mut levels = Vec::new();
+        let mut current = text.to_string();
+        while levels.len() < MAX_LEVELS {
+            let next = scheme.escape(&current);
+            if next == current {
+                break;
+            }
+            current = next;
+            levels.push(EscapeMismatch {
+                scheme,
+                direction: EscapeDirection::Escape,
+                levels: levels.len() + 1,
+                candidate: current.clone(),
+            });
+        }
+        levels
+    });
+
+    unescaped.chain(escaped)
+}
+
+// ============================================================================
+// DECODERS
+// ============================================================================
+
+/// Decode backslash escapes; `decode` handles the characters after the backslash
+///
+/// `decode` receives the remaining text (just past the backslash) and
+/// returns the decoded character and how many bytes it consumed.
+fn unescape_backslashes(text: &str, decode: fn(&str) -> Option<(char, usize)>) -> Option<String> {
+    if !text.contains('\\') {
+        return None;
+    }
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('\\') {
+        output.push_str(&rest[..index]);
+        let (c, consumed) = decode(&rest[index + 1..])?;
+        output.push(c);
+        rest = &rest[index + 1 + consumed..];
+    }
+    output.push_str(rest);
+    Some(output)
+}
+
+/// One JSON escape (after the backslash)
+fn json_escape(rest: &str) -> Option<(char, usize)> {
+    let c = match rest.chars().next()? {
+        '"' => '"',
+        '\\' => '\\',
+        '/' => '/',
+        'b' => '\u{8}',
+        'f' => '\u{C}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' => {
+            let high = hex_value(rest.get(1..5)?)?;
+            if !(0xD800..0xDC00).contains(&high) {
+                return Some((char::from_u32(high)?, 5));
+            }
+            // Surrogate pair, e.g. \uD83D\uDE00
+            let low = hex_value(rest.get(5..11)?.strip_prefix("\\u")?)?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return None;
+            }
+            let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+            return Some((char::from_u32(code)?, 11));
+        }
+        _ => return None,
+    };
+    Some((c, 1))
+}
+
+/// One Rust/C escape (after the backslash)
+fn c_escape(rest: &str) -> Option<(char, usize)> {
+    let c = match rest.chars().next()? {
+        '"' => '"',
+        '\'' => '\'',
+        '\\' => '\\',
+        '0' => '\0',
+        'a' => '\u{7}',
+        'b' => '\u{8}',
+        'f' => '\u{C}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'v' => '\u{B}',
+        'x' => return Some((char::from_u32(hex_value(rest.get(1..3)?)?)?, 3)),
+        'u' => {
+            let digits = rest.strip_prefix("u{")?;
+            let end = digits.find('}')?;
+            return Some((char::from_u32(hex_value(&digits[..end])?)?, end + 3));
+        }
+        _ => return None,
+    };
+    Some((c, 1))
+}
+
+/// Decode HTML entities; unknown named entities are an error
+fn unescape_html(text: &str) -> Option<String> {
+    if !text.contains('&') {
+        return None;
+    }
+    let mut output = String::with_capacity(text.len());
+    let mut decoded_any = false;
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+        output.push_str(&rest[..index]);
+        rest = &rest[index..];
+        // A bare '&' (as in `a && b`) is not an entity
+        let Some(end) = rest.find(';').filter(|&end| end <= 10) else {
+            output.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            "nbsp" => '\u{A0}',
+            _ => {
+                let code = if let Some(hex) = entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                {
+                    hex_value(hex)
+                } else if let Some(decimal) = entity.strip_prefix('#') {
+                    decimal.parse().ok()
+                } else {
+                    None
+                };
+                match code.and_then(char::from_u32) {
+                    Some(c) => c,
+                    None => {
+                        output.push('&');
+                        rest = &rest[1..];
+                        continue;
+                    }
+                }
+            }
+        };
+        output.push(c);
+        decoded_any = true;
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+    decoded_any.then_some(output)
+}
+
+/// Decode `%XX` sequences; the result must be valid UTF-8
+fn unescape_url(text: &str) -> Option<String> {
+    if !text.contains('%') {
+        return None;
+    }
+    let bytes = text.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = u8::from_str_radix(text.get(i + 1..i + 3)?, 16).ok()?;
+            output.push(byte);
+            i += 3;
+        } else {
+            output.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(output).ok()
+}
+
+/// Parse a run of hex digits (no sign, no prefix)
+fn hex_value(digits: &str) -> Option<u32> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
@@ -0,0 +1,303 @@
+//! Whole-file hygiene scanning
+//!
+//! [`CharCodeData`](crate::char_analysis::CharCodeData) explains why two
+//! strings differ; this module instead walks an entire file and reports
+//! every invisible or risky character with its line and column, so an agent
+//! can see what it is about to edit around before it writes a search string.
+
+use crate::char_analysis::{format_char_label, is_bidi_control, is_default_ignorable};
+use crate::line_endings::{LineEndingStyle, analyze_line_endings};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// PUBLIC TYPES
+// ============================================================================
+
+/// Category of a hygiene problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HygieneIssueKind {
+    /// Zero-width or other default-ignorable character
+    ZeroWidth,
+    /// Bidirectional control character (Trojan Source risk)
+    BidiControl,
+    /// Leading whitespace mixing tabs and spaces
+    MixedIndentation,
+    /// Spaces or tabs before the line ending
+    TrailingWhitespace,
+    /// Line ending that differs from the file's predominant style
+    MixedLineEndings,
+    /// U+FEFF anywhere except the very start of the file
+    MisplacedBom,
+    /// U+FFFD replacement character (earlier decoding damage)
+    ReplacementChar,
+    /// C0/C1 control character other than TAB, LF and CR
+    ControlChar,
+}
+
+impl HygieneIssueKind {
+    /// Short label used in summaries
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::ZeroWidth => "zero-width",
+            Self::BidiControl => "bidi control",
+            Self::MixedIndentation => "mixed indentation",
+            Self::TrailingWhitespace => "trailing whitespace",
+            Self::MixedLineEndings => "mixed line endings",
+            Self::MisplacedBom => "misplaced BOM",
+            Self::ReplacementChar => "replacement char",
+            Self::ControlChar => "control char",
+        }
+    }
+}
+
+/// One problem at a specific location
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct HygieneIssue {
+    pub kind: HygieneIssueKind,
+
+    /// 1-based line number
+    pub line: usize,
+
+    /// 1-based column, counted in characters
+    pub column: usize,
+
+    /// The offending character's code, for single-character issues
+    pub code: Option<u32>,
+
+    /// Human-readable description
+    pub message: String,
+}
+
+/// Result of scanning a whole file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct HygieneReport {
+    /// Number of lines scanned
+    pub line_count: usize,
+
+    /// Every issue found, in file order
+    pub issues: Vec<HygieneIssue>,
+}
+
+impl HygieneReport {
+    /// Whether no issues were found
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Number of issues of one kind
+    #[must_use]
+    pub fn count(&self, kind: HygieneIssueKind) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.kind == kind)
+            .count()
+    }
+
+    /// One-line summary, e.g. "3 issues in 120 lines: 2 trailing whitespace, 1 zero-width"
+    #[must_use]
+    pub fn summary(&self) -> String {
+        if self.is_clean() {
+            return format!("No issues in {} lines", self.line_count);
+        }
+
+        // Kinds in order of first appearance
+        let mut kinds: Vec<HygieneIssueKind> = Vec::new();
+        for issue in &self.issues {
+            if !kinds.contains(&issue.kind) {
+                kinds.push(issue.kind);
+            }
+        }
+        let counts: Vec<String> = kinds
+            .iter()
+            .map(|&kind| format!("{} {}", self.count(kind), kind.label()))
+            .collect();
+
+        format!(
+            "{} issue{} in {} lines: {}",
+            self.issues.len(),
+            if self.issues.len() == 1 { "" } else { "s" },
+            self.line_count,
+            counts.join(", ")
+        )
+    }
+
+    /// Summary followed by one `line:column: message` row per issue
+    #[must_use]
+    pub fn format(&self) -> String {
+        let mut output = self.summary();
+        output.push('\n');
+        for issue in &self.issues {
+            output.push_str(&format!(
+                "  {}:{}: {}\n",
+                issue.line, issue.column, issue.message
+            ));
+        }
+        output
+    }
+}
+
+// ============================================================================
+// SCANNER
+// ============================================================================
+
+/// Scan an entire file for invisible, risky or inconsistent characters
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::hygiene::{scan_file, HygieneIssueKind};
+///
+/// let report = scan_file("fn main() {  \n\t let x\u{200B} = 1;\n}\n");
+/// assert_eq!(report.issues.len(), 3);
+/// assert_eq!(report.issues[0].kind, HygieneIssueKind::TrailingWhitespace);
+/// assert_eq!((report.issues[0].line, report.issues[0].column), (1, 12));
+/// assert_eq!((report.issues[2].line, report.issues[2].column), (2, 8));
+/// ```
+#[must_use]
+pub fn scan_file(content: &str) -> HygieneReport {
+    let predominant = analyze_line_endings(content).style;
+    let mut report = HygieneReport::default();
+
+    for (index, (line, ending)) in split_lines(content).into_iter().enumerate() {
+        let line_number = index + 1;
+        report.line_count = line_number;
+
+        check_indentation(line, line_number, &mut report.issues);
+        check_trailing_whitespace(line, line_number, &mut report.issues);
+
+        for (column, c) in line.chars().enumerate() {
+            let at_file_start = line_number == 1 && column == 0;
+            if let Some(issue) = check_char(c, at_file_start) {
+                report.issues.push(HygieneIssue {
+                    kind: issue,
+                    line: line_number,
+                    column: column + 1,
+                    code: Some(u32::from(c)),
+                    message: char_message(issue, c),
+                });
+            }
+        }
+
+        if let Some(style) = ending
+            && style != predominant
+        {
+            report.issues.push(HygieneIssue {
+                kind: HygieneIssueKind::MixedLineEndings,
+                line: line_number,
+                column: line.chars().count() + 1,
+                code: None,
+                message: format!("{style:?} line ending in a {predominant:?} file"),
+            });
+        }
+    }
+
+    // Line-level checks run first; restore column order within each line
+    report
+        .issues
+        .sort_by_key(|issue| (issue.line, issue.column));
+    report
+}
+
+/// Split into lines, returning each line's content and terminator style
+fn split_lines(content: &str) -> Vec<(&str, Option<LineEndingStyle>)> {
+    let bytes = content.as_bytes();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                lines.push((&content[start..i], Some(LineEndingStyle::Crlf)));
+                i += 2;
+                start = i;
+            }
+            b'\r' => {
+                lines.push((&content[start..i], Some(LineEndingStyle::Cr)));
+                i += 1;
+                start = i;
+            }
+            b'\n' => {
+                lines.push((&content[start..i], Some(LineEndingStyle::Lf)));
+                i += 1;
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    if start < content.len() {
+        lines.push((&content[start..], None));
+    }
+    lines
+}
+
+/// Flag leading whitespace that mixes tabs and spaces
+fn check_indentation(line: &str, line_number: usize, issues: &mut Vec<HygieneIssue>) {
+    let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+    if indent.contains(' ') && indent.contains('\t') {
+        issues.push(HygieneIssue {
+            kind: HygieneIssueKind::MixedIndentation,
+            line: line_number,
+            column: 1,
+            code: None,
+            message: format!(
+                "indentation mixes tabs and spaces ({})",
+                indent
+                    .chars()
+                    .map(|c| if c == '\t' { "TAB" } else { "SPACE" })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        });
+    }
+}
+
+/// Flag spaces/tabs before the end of a line
+fn check_trailing_whitespace(line: &str, line_number: usize, issues: &mut Vec<HygieneIssue>) {
+    let content = line.trim_end_matches([' ', '\t']);
+    // Whitespace-only lines are reported too; their "trailing" part is the whole line
+    if content.len() < line.len() {
+        let trailing = line.len() - content.len();
+        issues.push(HygieneIssue {
+            kind: HygieneIssueKind::TrailingWhitespace,
+            line: line_number,
+            column: content.chars().count() + 1,
+            code: None,
+            message: format!(
+                "{trailing} trailing whitespace character{}",
+                if trailing == 1 { "" } else { "s" }
+            ),
+        });
+    }
+}
+
+/// Classify a single character, if it is a problem
+fn check_char(c: char, at_file_start: bool) -> Option<HygieneIssueKind> {
+    let code = u32::from(c);
+    match code {
+        0xFEFF if at_file_start => None,
+        0xFEFF => Some(HygieneIssueKind::MisplacedBom),
+        0xFFFD => Some(HygieneIssueKind::ReplacementChar),
+        9 => None,
+        0..=31 | 127..=159 => Some(HygieneIssueKind::ControlChar),
+        _ if is_bidi_control(c) => Some(HygieneIssueKind::BidiControl),
+        _ if is_default_ignorable(code) => Some(HygieneIssueKind::ZeroWidth),
+        _ => None,
+    }
+}
+
+/// Describe a character issue, e.g. "U+200B ZERO WIDTH SPACE (ZWSP): zero-width character"
+fn char_message(kind: HygieneIssueKind, c: char) -> String {
+    let what = match kind {
+        HygieneIssueKind::ZeroWidth => "zero-width character",
+        HygieneIssueKind::BidiControl => "bidirectional control character",
+        HygieneIssueKind::MisplacedBom => "byte order mark inside the file",
+        HygieneIssueKind::ReplacementChar => "replacement character (decoding damage)",
+        HygieneIssueKind::ControlChar => "control character",
+        _ => "character",
+    };
+    format!("{}: {what}", format_char_label(u32::from(c)))
+}
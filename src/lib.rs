@@ -1,10 +1,23 @@
+pub mod block_moves;
+pub mod bom;
 pub mod char_analysis;
 pub mod char_diff;
+pub mod confusables;
 pub mod edit_log;
+pub mod encoding;
+pub mod escaping;
 pub mod fuzzy_logger;
 pub mod fuzzy_search;
+pub mod hygiene;
 pub mod line_endings;
+pub mod merge;
+pub mod patch;
+pub mod repair;
+pub mod side_by_side;
+pub mod smart_punctuation;
 pub mod suggestions;
+pub mod terminal;
+pub mod tool_artefacts;
 pub mod usage_tracker;
 
 // Re-export commonly used types
@@ -12,7 +25,14 @@ pub use edit_log::{EditBlockLogEntry, EditBlockLogger, EditBlockResult, get_edit
 
 pub use fuzzy_logger::{FuzzyLogger, FuzzySearchLogEntry, get_logger};
 
+pub use merge::{MergeConflict, MergeResult, merge3};
+
+pub use patch::{Patch, PatchError, PatchOp};
+
 pub use char_analysis::{
-    CharCodeClassification, CharCodeData, CharDistribution, EncodingIssue, UnicodeAnalysis,
-    WhitespaceIssue,
+    ANALYSIS_SCHEMA_VERSION, AnalysisCache, AnalysisCacheConfig, AnalysisCacheStats,
+    AnalysisDocument, AnalysisJsonError, CachePolicy, CharCodeClassification, CharCodeData,
+    CharDistribution, CompatibilityMapping, EncodingIssue, Finding, FindingKind,
+    LineByLineAnalysis, LineComparison, LineStatus, SecurityIssue, Severity, UnicodeAnalysis,
+    WhitespaceIssue, get_analysis_cache,
 };
@@ -0,0 +1,238 @@
+//! Three-way merge for concurrent edits to the same file
+//!
+//! When two agents (or an agent and a human) edit a file from the same
+//! base, [`merge3`] combines both sets of changes line by line. Changes to
+//! different regions merge cleanly; overlapping changes become conflict
+//! regions marked with the standard `<<<<<<<` / `=======` / `>>>>>>>`
+//! markers and are also reported as structured [`MergeConflict`]s.
+
+use crate::char_diff::{DiffKind, diff_slices, split_lines};
+use std::ops::Range;
+
+// ============================================================================
+// PUBLIC TYPES
+// ============================================================================
+
+/// How conflict regions are written into the merged text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictStyle {
+    /// Ours and theirs only
+    #[default]
+    Merge,
+    /// Ours, base (after `|||||||`) and theirs
+    Diff3,
+}
+
+/// Labels and style for conflict markers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeOptions {
+    pub ours_label: String,
+    pub base_label: String,
+    pub theirs_label: String,
+    pub style: ConflictStyle,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            ours_label: "ours".to_string(),
+            base_label: "base".to_string(),
+            theirs_label: "theirs".to_string(),
+            style: ConflictStyle::Merge,
+        }
+    }
+}
+
+/// A region both sides changed differently
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// Zero-based line range in the base text
+    pub base_lines: Range<usize>,
+
+    /// Zero-based line range in our text
+    pub ours_lines: Range<usize>,
+
+    /// Zero-based line range in their text
+    pub theirs_lines: Range<usize>,
+
+    /// Zero-based line of the `<<<<<<<` marker in the merged text
+    pub merged_line: usize,
+
+    pub base: String,
+    pub ours: String,
+    pub theirs: String,
+}
+
+/// Result of a three-way merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeResult {
+    /// Merged text, with conflict markers around any conflicts
+    pub merged: String,
+
+    /// Conflicts in the order they appear in `merged`
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeResult {
+    /// Whether the merge completed without conflicts
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+// ============================================================================
+// MERGE IMPLEMENTATION
+// ============================================================================
+
+/// Merge two descendants of `base` with default labels (`ours` / `theirs`)
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::merge::merge3;
+///
+/// let base = "a\nb\nc\n";
+/// let ours = "A\nb\nc\n";
+/// let theirs = "a\nb\nC\n";
+///
+/// let result = merge3(base, ours, theirs);
+/// assert!(result.is_clean());
+/// assert_eq!(result.merged, "A\nb\nC\n");
+/// ```
+#[must_use]
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> MergeResult {
+    merge3_with_options(base, ours, theirs, &MergeOptions::default())
+}
+
+/// Merge two descendants of `base` with custom conflict labels and style
+#[must_use]
+pub fn merge3_with_options(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    options: &MergeOptions,
+) -> MergeResult {
+    let base_lines = split_lines(base);
+    let ours_lines = split_lines(ours);
+    let theirs_lines = split_lines(theirs);
+
+    // For each base line, the matching line in each descendant (if unchanged)
+    let ours_match = match_lines(&base_lines, &ours_lines);
+    let theirs_match = match_lines(&base_lines, &theirs_lines);
+
+    let mut merged = String::with_capacity(base.len().max(ours.len()).max(theirs.len()));
+    let mut merged_line_count = 0;
+    let mut conflicts = Vec::new();
+
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    loop {
+        // Stable line: unchanged on both sides
+        if i < base_lines.len() && ours_match[i] == Some(j) && theirs_match[i] == Some(k) {
+            merged.push_str(base_lines[i]);
+            merged_line_count += 1;
+            i += 1;
+            j += 1;
+            k += 1;
+            continue;
+        }
+
+        // Next base line both sides kept, or the end of all three texts
+        let (next_i, next_j, next_k) = (i..base_lines.len())
+            .find_map(|index| match (ours_match[index], theirs_match[index]) {
+                (Some(o), Some(t)) => Some((index, o, t)),
+                _ => None,
+            })
+            .unwrap_or((base_lines.len(), ours_lines.len(), theirs_lines.len()));
+
+        if (next_i, next_j, next_k) == (i, j, k) {
+            break;
+        }
+
+        let base_chunk = &base_lines[i..next_i];
+        let ours_chunk = &ours_lines[j..next_j];
+        let theirs_chunk = &theirs_lines[k..next_k];
+
+        let resolved = if ours_chunk == base_chunk {
+            Some(theirs_chunk)
+        } else if theirs_chunk == base_chunk || ours_chunk == theirs_chunk {
+            Some(ours_chunk)
+        } else {
+            None
+        };
+
+        if let Some(chunk) = resolved {
+            merged.push_str(&chunk.concat());
+            merged_line_count += chunk.len();
+        } else {
+            let conflict = MergeConflict {
+                base_lines: i..next_i,
+                ours_lines: j..next_j,
+                theirs_lines: k..next_k,
+                merged_line: merged_line_count,
+                base: base_chunk.concat(),
+                ours: ours_chunk.concat(),
+                theirs: theirs_chunk.concat(),
+            };
+            merged_line_count += write_conflict(&mut merged, &conflict, options);
+            conflicts.push(conflict);
+        }
+
+        i = next_i;
+        j = next_j;
+        k = next_k;
+    }
+
+    MergeResult { merged, conflicts }
+}
+
+/// Map each line of `base` to its position in `other` when unchanged
+fn match_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    let (mut bi, mut oi) = (0, 0);
+    for (kind, len) in diff_slices(base, other) {
+        match kind {
+            DiffKind::Equal => {
+                for offset in 0..len {
+                    matches[bi + offset] = Some(oi + offset);
+                }
+                bi += len;
+                oi += len;
+            }
+            DiffKind::Delete => bi += len,
+            DiffKind::Insert => oi += len,
+        }
+    }
+    matches
+}
+
+/// Write a conflict region with markers, returning the number of lines written
+fn write_conflict(merged: &mut String, conflict: &MergeConflict, options: &MergeOptions) -> usize {
+    let mut lines = 0;
+    let mut section = |merged: &mut String, marker: &str, text: &str| {
+        merged.push_str(marker);
+        merged.push('\n');
+        merged.push_str(text);
+        if !text.is_empty() && !text.ends_with('\n') {
+            merged.push('\n');
+        }
+        lines += 1 + text.lines().count();
+    };
+
+    section(
+        merged,
+        &format!("<<<<<<< {}", options.ours_label),
+        &conflict.ours,
+    );
+    if options.style == ConflictStyle::Diff3 {
+        section(
+            merged,
+            &format!("||||||| {}", options.base_label),
+            &conflict.base,
+        );
+    }
+    section(merged, "=======", &conflict.theirs);
+    merged.push_str(&format!(">>>>>>> {}\n", options.theirs_label));
+
+    lines + 1
+}
@@ -0,0 +1,430 @@
+//! Serialisable, invertible patches produced by the diff engine
+//!
+//! A [`Patch`] stores the edit script between two strings compactly:
+//! unchanged runs as character counts, deleted and inserted text verbatim.
+//! Because deletions keep their text, a patch can be inverted for undo and
+//! consecutive patches can be composed into one.
+
+use crate::char_diff::{
+    CharDiff, DEFAULT_EDIT_COST, DiffKind, DiffOp, cleanup_efficiency, diff_chars,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// ============================================================================
+// PATCH TYPES
+// ============================================================================
+
+/// A single patch operation
+///
+/// Lengths and offsets are counted in characters, not bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchOp {
+    /// Keep the next `n` characters unchanged
+    Retain(usize),
+    /// Remove this text (must match the original)
+    Delete(String),
+    /// Add this text
+    Insert(String),
+}
+
+/// Edit script that turns one string into another
+///
+/// Deserialised patches are normalised like constructed ones: zero-length
+/// operations are dropped and adjacent operations of the same kind merged.
+/// Patches whose lengths overflow `usize` are rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "PatchRepr")]
+pub struct Patch {
+    ops: Vec<PatchOp>,
+}
+
+/// Wire form of [`Patch`], normalised on the way in
+#[derive(Deserialize)]
+struct PatchRepr {
+    ops: Vec<PatchOp>,
+}
+
+impl TryFrom<PatchRepr> for Patch {
+    type Error = PatchError;
+
+    fn try_from(repr: PatchRepr) -> Result<Self, Self::Error> {
+        // Checked once here so base_len, target_len and merged retains of
+        // the normalised patch cannot overflow
+        let mut base_len = 0usize;
+        let mut target_len = 0usize;
+        for op in &repr.ops {
+            let (base, target) = match op {
+                PatchOp::Retain(n) => (*n, *n),
+                PatchOp::Delete(text) => (text.chars().count(), 0),
+                PatchOp::Insert(text) => (0, text.chars().count()),
+            };
+            base_len = base_len
+                .checked_add(base)
+                .ok_or(PatchError::LengthOverflow)?;
+            target_len = target_len
+                .checked_add(target)
+                .ok_or(PatchError::LengthOverflow)?;
+        }
+
+        let mut patch = Self::default();
+        for op in repr.ops {
+            match op {
+                PatchOp::Retain(n) => patch.retain(n),
+                PatchOp::Delete(text) => patch.delete(&text),
+                PatchOp::Insert(text) => patch.insert(&text),
+            }
+        }
+        Ok(patch)
+    }
+}
+
+/// Errors from applying or composing patches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// The input's length does not match the patch's base length
+    LengthMismatch { expected: usize, actual: usize },
+
+    /// Text at `offset` differs from the text the patch deletes
+    DeletedTextMismatch {
+        offset: usize,
+        expected: String,
+        actual: String,
+    },
+
+    /// A deserialised patch's lengths do not fit in `usize`
+    LengthOverflow,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "patch expects input of {expected} characters, got {actual}"
+            ),
+            Self::DeletedTextMismatch {
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "patch expects {expected:?} at character {offset}, found {actual:?}"
+            ),
+            Self::LengthOverflow => write!(f, "patch length overflows usize"),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+// ============================================================================
+// CONSTRUCTION
+// ============================================================================
+
+impl Patch {
+    /// Diff `original` against `modified` and record the result as a patch
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kodegen_utils::patch::Patch;
+    ///
+    /// let patch = Patch::new("let x = 1;", "let y = 2;");
+    /// assert_eq!(patch.apply("let x = 1;").unwrap(), "let y = 2;");
+    /// assert_eq!(patch.invert().apply("let y = 2;").unwrap(), "let x = 1;");
+    /// ```
+    #[must_use]
+    pub fn new(original: &str, modified: &str) -> Self {
+        let mut ops = diff_chars(original, modified);
+        cleanup_efficiency(&mut ops, DEFAULT_EDIT_COST);
+        Self::from_diff_ops(&ops)
+    }
+
+    /// Build a patch from a diff engine edit script
+    #[must_use]
+    pub fn from_diff_ops(ops: &[DiffOp]) -> Self {
+        let mut patch = Self::default();
+        for op in ops {
+            match op.kind {
+                DiffKind::Equal => patch.retain(op.text.chars().count()),
+                DiffKind::Delete => patch.delete(&op.text),
+                DiffKind::Insert => patch.insert(&op.text),
+            }
+        }
+        patch
+    }
+
+    /// Operations in order
+    #[must_use]
+    pub fn ops(&self) -> &[PatchOp] {
+        &self.ops
+    }
+
+    /// Length in characters of the string this patch applies to
+    #[must_use]
+    pub fn base_len(&self) -> usize {
+        self.ops
+            .iter()
+            .map(|op| match op {
+                PatchOp::Retain(n) => *n,
+                PatchOp::Delete(text) => text.chars().count(),
+                PatchOp::Insert(_) => 0,
+            })
+            .sum()
+    }
+
+    /// Length in characters of the string this patch produces
+    #[must_use]
+    pub fn target_len(&self) -> usize {
+        self.ops
+            .iter()
+            .map(|op| match op {
+                PatchOp::Retain(n) => *n,
+                PatchOp::Insert(text) => text.chars().count(),
+                PatchOp::Delete(_) => 0,
+            })
+            .sum()
+    }
+
+    /// Whether applying this patch leaves the input unchanged
+    #[must_use]
+    pub fn is_identity(&self) -> bool {
+        self.ops.iter().all(|op| matches!(op, PatchOp::Retain(_)))
+    }
+
+    /// Append a retain, merging with a trailing retain
+    fn retain(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        if let Some(PatchOp::Retain(last)) = self.ops.last_mut() {
+            *last += n;
+        } else {
+            self.ops.push(PatchOp::Retain(n));
+        }
+    }
+
+    /// Append an insert, merging with a trailing insert
+    fn insert(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(PatchOp::Insert(last)) = self.ops.last_mut() {
+            last.push_str(text);
+        } else {
+            self.ops.push(PatchOp::Insert(text.to_string()));
+        }
+    }
+
+    /// Append a delete, keeping deletes ahead of inserts within a change
+    fn delete(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let len = self.ops.len();
+        let insert_index = match self.ops.last() {
+            Some(PatchOp::Insert(_)) => len - 1,
+            _ => len,
+        };
+        if insert_index > 0
+            && let PatchOp::Delete(previous) = &mut self.ops[insert_index - 1]
+        {
+            previous.push_str(text);
+        } else {
+            self.ops
+                .insert(insert_index, PatchOp::Delete(text.to_string()));
+        }
+    }
+}
+
+impl CharDiff {
+    /// Convert this diff into an applicable patch
+    #[must_use]
+    pub fn to_patch(&self) -> Patch {
+        Patch::from_diff_ops(&self.ops())
+    }
+}
+
+// ============================================================================
+// APPLY / INVERT / COMPOSE
+// ============================================================================
+
+impl Patch {
+    /// Apply the patch to `original`, verifying every deleted span
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PatchError::LengthMismatch`] if `original` is not the
+    /// length the patch was made for, or [`PatchError::DeletedTextMismatch`]
+    /// if the text to be deleted differs from what the patch recorded.
+    pub fn apply(&self, original: &str) -> Result<String, PatchError> {
+        let actual_len = original.chars().count();
+        let base_len = self.base_len();
+        if actual_len != base_len {
+            return Err(PatchError::LengthMismatch {
+                expected: base_len,
+                actual: actual_len,
+            });
+        }
+
+        let mut output = String::with_capacity(original.len());
+        let mut chars = original.chars();
+        let mut offset = 0;
+
+        for op in &self.ops {
+            match op {
+                PatchOp::Retain(n) => {
+                    output.extend(chars.by_ref().take(*n));
+                    offset += n;
+                }
+                PatchOp::Delete(text) => {
+                    let len = text.chars().count();
+                    let actual: String = chars.by_ref().take(len).collect();
+                    if actual != *text {
+                        return Err(PatchError::DeletedTextMismatch {
+                            offset,
+                            expected: text.clone(),
+                            actual,
+                        });
+                    }
+                    offset += len;
+                }
+                PatchOp::Insert(text) => output.push_str(text),
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Build the patch that undoes this one
+    #[must_use]
+    pub fn invert(&self) -> Self {
+        let mut inverted = Self::default();
+        for op in &self.ops {
+            match op {
+                PatchOp::Retain(n) => inverted.retain(*n),
+                PatchOp::Delete(text) => inverted.insert(text),
+                PatchOp::Insert(text) => inverted.delete(text),
+            }
+        }
+        inverted
+    }
+
+    /// Combine this patch with one applied to its output
+    ///
+    /// `a.compose(&b)?.apply(s)` equals `b.apply(&a.apply(s)?)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PatchError::LengthMismatch`] if `next` was not made for
+    /// this patch's output, or [`PatchError::DeletedTextMismatch`] if `next`
+    /// deletes text that this patch inserted differently.
+    pub fn compose(&self, next: &Self) -> Result<Self, PatchError> {
+        if self.target_len() != next.base_len() {
+            return Err(PatchError::LengthMismatch {
+                expected: next.base_len(),
+                actual: self.target_len(),
+            });
+        }
+
+        let mut composed = Self::default();
+        let mut first = self.ops.iter().cloned();
+        let mut second = next.ops.iter().cloned();
+        let mut op1 = first.next();
+        let mut op2 = second.next();
+        // Character offset into the intermediate string, for error reporting
+        let mut offset = 0;
+
+        loop {
+            match (op1.take(), op2.take()) {
+                (None, None) => break,
+
+                // Deletions from the first patch never reach the second
+                (Some(PatchOp::Delete(text)), pending) => {
+                    composed.delete(&text);
+                    op1 = first.next();
+                    op2 = pending;
+                }
+
+                // Insertions from the second patch don't consume the first
+                (pending, Some(PatchOp::Insert(text))) => {
+                    composed.insert(&text);
+                    op1 = pending;
+                    op2 = second.next();
+                }
+
+                (Some(PatchOp::Retain(n)), Some(PatchOp::Retain(m))) => {
+                    let len = n.min(m);
+                    composed.retain(len);
+                    offset += len;
+                    op1 = remaining_retain(n - len).or_else(|| first.next());
+                    op2 = remaining_retain(m - len).or_else(|| second.next());
+                }
+
+                // Text inserted by the first patch and deleted by the second
+                (Some(PatchOp::Insert(inserted)), Some(PatchOp::Delete(deleted))) => {
+                    let len = inserted.chars().count().min(deleted.chars().count());
+                    let (inserted_head, inserted_tail) = split_chars(&inserted, len);
+                    let (deleted_head, deleted_tail) = split_chars(&deleted, len);
+                    if inserted_head != deleted_head {
+                        return Err(PatchError::DeletedTextMismatch {
+                            offset,
+                            expected: deleted_head.to_string(),
+                            actual: inserted_head.to_string(),
+                        });
+                    }
+                    offset += len;
+                    op1 = remaining_insert(inserted_tail).or_else(|| first.next());
+                    op2 = remaining_delete(deleted_tail).or_else(|| second.next());
+                }
+
+                (Some(PatchOp::Insert(inserted)), Some(PatchOp::Retain(m))) => {
+                    let len = inserted.chars().count().min(m);
+                    let (head, tail) = split_chars(&inserted, len);
+                    composed.insert(head);
+                    offset += len;
+                    op1 = remaining_insert(tail).or_else(|| first.next());
+                    op2 = remaining_retain(m - len).or_else(|| second.next());
+                }
+
+                (Some(PatchOp::Retain(n)), Some(PatchOp::Delete(deleted))) => {
+                    let len = n.min(deleted.chars().count());
+                    let (head, tail) = split_chars(&deleted, len);
+                    composed.delete(head);
+                    offset += len;
+                    op1 = remaining_retain(n - len).or_else(|| first.next());
+                    op2 = remaining_delete(tail).or_else(|| second.next());
+                }
+
+                // Lengths were checked up front, so both sides run out together
+                (Some(_), None) | (None, Some(_)) => unreachable!("patch lengths verified"),
+            }
+        }
+
+        Ok(composed)
+    }
+}
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+/// Split a string after `n` characters
+fn split_chars(s: &str, n: usize) -> (&str, &str) {
+    let index = s.char_indices().nth(n).map_or(s.len(), |(i, _)| i);
+    s.split_at(index)
+}
+
+fn remaining_retain(n: usize) -> Option<PatchOp> {
+    (n > 0).then_some(PatchOp::Retain(n))
+}
+
+fn remaining_insert(text: &str) -> Option<PatchOp> {
+    (!text.is_empty()).then(|| PatchOp::Insert(text.to_string()))
+}
+
+fn remaining_delete(text: &str) -> Option<PatchOp> {
+    (!text.is_empty()).then(|| PatchOp::Delete(text.to_string()))
+}
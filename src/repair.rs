@@ -0,0 +1,388 @@
+//! Automatic repair of failed `edit_block` searches
+//!
+//! Where [`CharCodeData`](crate::char_analysis::CharCodeData) explains *why*
+//! a search string failed, this module fixes it: the transformations the
+//! diagnoses point at (NFC/NFD/NFKC normalisation, invisible-character
+//! stripping, tab/space conversion, line-ending conversion, smart punctuation
+//! folding or restoring) are tried against the file, and only one that produces an exact,
+//! unique match is accepted. The same transformation is then applied to the
+//! replacement text so it follows the file's conventions too.
+
+use crate::char_analysis::strip_invisible_chars;
+use crate::line_endings::{LineEndingStyle, analyze_line_endings, normalize_line_endings};
+use crate::smart_punctuation::{asciify, fix_search_string, has_smart_punctuation};
+use unicode_normalization::{UnicodeNormalization, is_nfc, is_nfd, is_nfkc};
+
+/// Indent widths tried when converting between tabs and spaces
+const INDENT_WIDTHS: &[usize] = &[4, 2, 8];
+
+// ============================================================================
+// TRANSFORMS
+// ============================================================================
+
+/// A text transformation the repair engine can apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairTransform {
+    /// Unicode NFC normalisation (composed)
+    Nfc,
+
+    /// Unicode NFD normalisation (decomposed)
+    Nfd,
+
+    /// Unicode NFKC normalisation (compatibility characters folded)
+    Nfkc,
+
+    /// Remove zero-width and other default-ignorable characters
+    StripInvisible,
+
+    /// Replace leading tabs with `width` spaces each
+    TabsToSpaces { width: usize },
+
+    /// Replace leading runs of `width` spaces with tabs
+    SpacesToTabs { width: usize },
+
+    /// Convert every line ending to the given style
+    LineEndings(LineEndingStyle),
+
+    /// Replace curly quotes, dashes and ellipses with ASCII
+    AsciifyPunctuation,
+
+    /// Use the file's curly quotes, dashes and ellipses where the search
+    /// has ASCII
+    ///
+    /// The search string takes the file's exact spelling. Other text gets
+    /// straight quotes curled by context, for the quote kinds the matched
+    /// region used. Dashes and ellipses are left as they are: in new text
+    /// `--` and `...` are as likely to be code (`i--`, `--flag`, `..=`) as
+    /// prose, and nothing says which dash the file would use.
+    SmartenPunctuation {
+        double_quotes: bool,
+        single_quotes: bool,
+    },
+}
+
+impl RepairTransform {
+    /// Apply the transformation to `text`
+    #[must_use]
+    pub fn apply(&self, text: &str) -> String {
+        match *self {
+            Self::Nfc => text.nfc().collect(),
+            Self::Nfd => text.nfd().collect(),
+            Self::Nfkc => text.nfkc().collect(),
+            Self::StripInvisible => strip_invisible_chars(text),
+            Self::TabsToSpaces { width } => {
+                map_indentation(text, |indent| indent.replace('\t', &" ".repeat(width)))
+            }
+            Self::SpacesToTabs { width } => map_indentation(text, |indent| {
+                let unit = " ".repeat(width);
+                let mut converted = String::new();
+                let mut rest = indent;
+                loop {
+                    if let Some(tail) = rest.strip_prefix(&unit) {
+                        converted.push('\t');
+                        rest = tail;
+                    } else if let Some(tail) = rest.strip_prefix('\t') {
+                        converted.push('\t');
+                        rest = tail;
+                    } else {
+                        break;
+                    }
+                }
+                converted.push_str(rest);
+                converted
+            }),
+            Self::LineEndings(style) => normalize_line_endings(text, style),
+            Self::AsciifyPunctuation => asciify(text).text,
+            Self::SmartenPunctuation {
+                double_quotes,
+                single_quotes,
+            } => curl_quotes(text, double_quotes, single_quotes),
+        }
+    }
+
+    /// Short human-readable description
+    #[must_use]
+    pub fn description(&self) -> String {
+        match self {
+            Self::Nfc => "normalized Unicode to NFC".to_string(),
+            Self::Nfd => "normalized Unicode to NFD".to_string(),
+            Self::Nfkc => "normalized Unicode to NFKC".to_string(),
+            Self::StripInvisible => "removed zero-width characters".to_string(),
+            Self::TabsToSpaces { width } => format!("converted tabs to {width} spaces"),
+            Self::SpacesToTabs { width } => format!("converted {width} spaces to tabs"),
+            Self::LineEndings(style) => format!("converted line endings to {style:?}"),
+            Self::AsciifyPunctuation => "replaced smart punctuation with ASCII".to_string(),
+            Self::SmartenPunctuation { .. } => "used the file's smart punctuation".to_string(),
+        }
+    }
+}
+
+/// Curl straight quotes: opening after whitespace or an opening bracket,
+/// closing (and apostrophe) otherwise
+fn curl_quotes(text: &str, double_quotes: bool, single_quotes: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut previous: Option<char> = None;
+    for c in text.chars() {
+        let opening =
+            previous.is_none_or(|p| p.is_whitespace() || "([{\u{201C}\u{2018}".contains(p));
+        let curled = match c {
+            '"' if double_quotes => {
+                if opening {
+                    '\u{201C}'
+                } else {
+                    '\u{201D}'
+                }
+            }
+            '\'' if single_quotes => {
+                if opening {
+                    '\u{2018}'
+                } else {
+                    '\u{2019}'
+                }
+            }
+            _ => c,
+        };
+        output.push(curled);
+        previous = Some(curled);
+    }
+    output
+}
+
+/// Rewrite the leading whitespace of every line
+fn map_indentation(text: &str, convert: impl Fn(&str) -> String) -> String {
+    let mut output = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_start_matches([' ', '\t']);
+        output.push_str(&convert(&line[..line.len() - content.len()]));
+        output.push_str(content);
+    }
+    output
+}
+
+// ============================================================================
+// REPAIR
+// ============================================================================
+
+/// A verified correction of a search string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    /// Transformations applied, in order (empty if the search already matched)
+    pub transforms: Vec<RepairTransform>,
+
+    /// Corrected search string; occurs exactly once in the file
+    pub search: String,
+
+    /// Byte offset of the match in the file
+    pub offset: usize,
+}
+
+impl Repair {
+    /// Apply the same transformations to other text (typically `new_string`)
+    #[must_use]
+    pub fn apply(&self, text: &str) -> String {
+        self.transforms
+            .iter()
+            .fold(text.to_string(), |acc, transform| transform.apply(&acc))
+    }
+
+    /// Describe the repair, e.g. "removed zero-width characters, converted tabs to 4 spaces"
+    #[must_use]
+    pub fn description(&self) -> String {
+        if self.transforms.is_empty() {
+            return "no repair needed".to_string();
+        }
+        self.transforms
+            .iter()
+            .map(RepairTransform::description)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Find a transformation of `search` that matches `content` exactly once
+///
+/// Candidate transformations are chosen from what differs between the
+/// search string and the file (invisible characters, smart punctuation,
+/// line endings, normalisation form, indentation). Each is tried alone
+/// first, then all together. Returns `None` if nothing yields a unique
+/// exact match.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::repair::repair_search;
+///
+/// let file = "fn main() {\n    println!(\"hi\");\n}\n";
+/// let repair = repair_search("fn main() {\n\tprintln!(\u{201C}hi\u{201D});", file).unwrap();
+///
+/// assert_eq!(repair.search, "fn main() {\n    println!(\"hi\");");
+/// assert_eq!(repair.apply("\tprintln!(\u{201C}bye\u{201D});"), "    println!(\"bye\");");
+/// ```
+#[must_use]
+pub fn repair_search(search: &str, content: &str) -> Option<Repair> {
+    if search.is_empty() {
+        return None;
+    }
+
+    let groups = candidate_groups(search, content);
+
+    // Single transformations, then one alternative from every group combined
+    let singles = groups.iter().flatten().map(|&t| vec![t]);
+    let combined = combinations(&groups).into_iter().filter(|c| c.len() > 1);
+
+    std::iter::once(Vec::new())
+        .chain(singles)
+        .chain(combined)
+        .find_map(|transforms| verify(search, content, transforms))
+}
+
+/// Candidate transformations, grouped so alternatives are never combined
+fn candidate_groups(search: &str, content: &str) -> Vec<Vec<RepairTransform>> {
+    let mut groups = Vec::new();
+
+    if strip_invisible_chars(search) != search {
+        groups.push(vec![RepairTransform::StripInvisible]);
+    }
+
+    if search.contains(['\r', '\n']) {
+        let style = analyze_line_endings(content).style;
+        let search_endings = analyze_line_endings(search);
+        if search_endings.has_mixed || search_endings.style != style {
+            groups.push(vec![RepairTransform::LineEndings(style)]);
+        }
+    }
+
+    let mut forms = Vec::new();
+    if !is_nfc(search) {
+        forms.push(RepairTransform::Nfc);
+    }
+    if !is_nfd(search) {
+        forms.push(RepairTransform::Nfd);
+    }
+    if !is_nfkc(search) {
+        forms.push(RepairTransform::Nfkc);
+    }
+    if !forms.is_empty() {
+        groups.push(forms);
+    }
+
+    let search_tabs = has_indent(search, '\t');
+    let search_spaces = has_indent(search, ' ');
+    let content_tabs = has_indent(content, '\t');
+    let content_spaces = has_indent(content, ' ');
+    let mut indents = Vec::new();
+    for width in indent_widths(content) {
+        if search_tabs && content_spaces {
+            indents.push(RepairTransform::TabsToSpaces { width });
+        }
+        if search_spaces && content_tabs {
+            indents.push(RepairTransform::SpacesToTabs { width });
+        }
+    }
+    if !indents.is_empty() {
+        groups.push(indents);
+    }
+
+    // Opposite directions, so never combined. Last, because smartening
+    // looks the candidate up in the file and needs every other transform
+    // applied first
+    let mut punctuation = Vec::new();
+    if has_smart_punctuation(search) {
+        punctuation.push(RepairTransform::AsciifyPunctuation);
+    }
+    if has_smart_punctuation(content) {
+        punctuation.push(RepairTransform::SmartenPunctuation {
+            double_quotes: false,
+            single_quotes: false,
+        });
+    }
+    if !punctuation.is_empty() {
+        groups.push(punctuation);
+    }
+
+    groups
+}
+
+/// Every way of picking one transform from each group
+fn combinations(groups: &[Vec<RepairTransform>]) -> Vec<Vec<RepairTransform>> {
+    groups.iter().fold(vec![Vec::new()], |acc, group| {
+        acc.iter()
+            .flat_map(|prefix| {
+                group.iter().map(move |&transform| {
+                    let mut combined = prefix.clone();
+                    combined.push(transform);
+                    combined
+                })
+            })
+            .collect()
+    })
+}
+
+/// Accept `transforms` if the transformed search occurs exactly once
+///
+/// [`RepairTransform::SmartenPunctuation`] takes the file's spelling of the
+/// search and records which quote kinds it uses.
+fn verify(search: &str, content: &str, mut transforms: Vec<RepairTransform>) -> Option<Repair> {
+    let mut candidate = search.to_string();
+    for transform in &mut transforms {
+        candidate = match transform {
+            RepairTransform::SmartenPunctuation {
+                double_quotes,
+                single_quotes,
+            } => {
+                let fixed = fix_search_string(content, &candidate)?;
+                *double_quotes = fixed.contains(['\u{201C}', '\u{201D}']);
+                *single_quotes = fixed.contains(['\u{2018}', '\u{2019}']);
+                fixed
+            }
+            _ => transform.apply(&candidate),
+        };
+    }
+    if candidate.is_empty() {
+        return None;
+    }
+
+    let mut matches = content.match_indices(candidate.as_str());
+    let (offset, _) = matches.next()?;
+    if matches.next().is_some() {
+        return None;
+    }
+
+    Some(Repair {
+        transforms,
+        search: candidate,
+        offset,
+    })
+}
+
+/// Whether any line of `text` is indented with `c`
+fn has_indent(text: &str, c: char) -> bool {
+    text.lines().any(|line| line.starts_with(c))
+}
+
+/// Indent widths to try, most likely first
+///
+/// The greatest common divisor of the file's space indents (when it is a
+/// plausible width) goes first, followed by the usual widths.
+fn indent_widths(content: &str) -> Vec<usize> {
+    let inferred = content
+        .lines()
+        .map(|line| line.len() - line.trim_start_matches(' ').len())
+        .filter(|&indent| indent > 0)
+        .fold(0, gcd);
+
+    let mut widths = Vec::new();
+    if (2..=8).contains(&inferred) {
+        widths.push(inferred);
+    }
+    for &width in INDENT_WIDTHS {
+        if !widths.contains(&width) {
+            widths.push(width);
+        }
+    }
+    widths
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
@@ -0,0 +1,285 @@
+//! Two-column side-by-side diff rendering for terminal review
+//!
+//! Built on the line hunks from [`crate::char_diff::diff_hunks`]: unchanged
+//! lines are aligned across both columns, changed lines are paired up row
+//! by row, and long lines wrap within their column. Widths account for
+//! tabs (at a configurable tab stop) and for wide CJK and emoji characters.
+//!
+//! The gutter between columns follows `sdiff`: `|` for a changed line,
+//! `<` for a line only on the left, `>` for a line only on the right.
+
+use crate::char_diff::{diff_hunks, split_lines};
+use std::fmt::Write;
+use unicode_width::UnicodeWidthChar;
+
+/// Layout options for [`render_side_by_side`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SideBySideOptions {
+    /// Total output width in terminal columns
+    pub width: usize,
+
+    /// Tab stop interval used to expand tabs
+    pub tab_stop: usize,
+
+    /// Wrap long lines within their column (otherwise truncate with `…`)
+    pub wrap: bool,
+
+    /// Show line numbers before each column
+    pub line_numbers: bool,
+}
+
+impl Default for SideBySideOptions {
+    fn default() -> Self {
+        Self {
+            width: 120,
+            tab_stop: 4,
+            wrap: true,
+            line_numbers: true,
+        }
+    }
+}
+
+/// Render `expected` (left) and `actual` (right) side by side
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::side_by_side::{SideBySideOptions, render_side_by_side};
+///
+/// let options = SideBySideOptions {
+///     width: 30,
+///     line_numbers: false,
+///     ..SideBySideOptions::default()
+/// };
+/// let output = render_side_by_side("a\nb\n", "a\nc\n", &options);
+/// assert_eq!(output, "a               a\nb             | c\n");
+/// ```
+#[must_use]
+pub fn render_side_by_side(expected: &str, actual: &str, options: &SideBySideOptions) -> String {
+    let old_lines = split_lines(expected);
+    let new_lines = split_lines(actual);
+
+    let number_width = if options.line_numbers {
+        old_lines
+            .len()
+            .max(new_lines.len())
+            .max(1)
+            .to_string()
+            .len()
+    } else {
+        0
+    };
+    // Fixed columns: "{num} " on both sides plus " {gutter} " in the middle
+    let fixed = if options.line_numbers {
+        2 * (number_width + 1) + 3
+    } else {
+        3
+    };
+    let column_width = (options.width.saturating_sub(fixed) / 2).max(1);
+
+    let layout = Layout {
+        options,
+        number_width,
+        column_width,
+    };
+
+    let mut output = String::new();
+    let (mut oi, mut ni) = (0, 0);
+    for hunk in diff_hunks(expected, actual) {
+        // Unchanged lines before this hunk
+        while oi < hunk.old_lines.start {
+            layout.push_row(
+                &mut output,
+                Some((oi, old_lines[oi])),
+                ' ',
+                Some((ni, new_lines[ni])),
+            );
+            oi += 1;
+            ni += 1;
+        }
+
+        // Changed lines, paired row by row
+        let rows = hunk.old_lines.len().max(hunk.new_lines.len());
+        for row in 0..rows {
+            let left = (row < hunk.old_lines.len()).then(|| (oi + row, old_lines[oi + row]));
+            let right = (row < hunk.new_lines.len()).then(|| (ni + row, new_lines[ni + row]));
+            let gutter = match (left, right) {
+                (Some(_), Some(_)) => '|',
+                (Some(_), None) => '<',
+                _ => '>',
+            };
+            layout.push_row(&mut output, left, gutter, right);
+        }
+        oi = hunk.old_lines.end;
+        ni = hunk.new_lines.end;
+    }
+    while oi < old_lines.len() && ni < new_lines.len() {
+        layout.push_row(
+            &mut output,
+            Some((oi, old_lines[oi])),
+            ' ',
+            Some((ni, new_lines[ni])),
+        );
+        oi += 1;
+        ni += 1;
+    }
+
+    output
+}
+
+/// Display width of a single line in terminal columns
+///
+/// Tabs advance to the next multiple of `tab_stop`; wide characters count
+/// as two columns; zero-width and combining characters count as zero.
+#[must_use]
+pub fn display_width(line: &str, tab_stop: usize) -> usize {
+    expand_line(line, tab_stop)
+        .iter()
+        .map(|cell| cell.width)
+        .sum()
+}
+
+// ============================================================================
+// INTERNAL LAYOUT
+// ============================================================================
+
+struct Layout<'a> {
+    options: &'a SideBySideOptions,
+    number_width: usize,
+    column_width: usize,
+}
+
+/// A display cell: text plus the terminal columns it occupies
+struct Cell {
+    text: String,
+    width: usize,
+}
+
+impl Layout<'_> {
+    /// Write one logical row, wrapping each side into as many output lines as needed
+    fn push_row(
+        &self,
+        output: &mut String,
+        left: Option<(usize, &str)>,
+        gutter: char,
+        right: Option<(usize, &str)>,
+    ) {
+        let left_chunks = left.map_or_else(Vec::new, |(_, line)| self.chunks(line));
+        let right_chunks = right.map_or_else(Vec::new, |(_, line)| self.chunks(line));
+        let rows = left_chunks.len().max(right_chunks.len()).max(1);
+
+        for row in 0..rows {
+            let mut line = String::new();
+            self.push_number(&mut line, left.filter(|_| row == 0).map(|(n, _)| n));
+            let (text, width) = left_chunks
+                .get(row)
+                .map_or(("", 0), |(text, width)| (text.as_str(), *width));
+            line.push_str(text);
+            // Trailing whitespace in the lines themselves is content, not padding
+            let mut content_end = line.len();
+            line.push_str(&" ".repeat(self.column_width.saturating_sub(width)));
+
+            line.push(' ');
+            line.push(gutter);
+            line.push(' ');
+
+            self.push_number(&mut line, right.filter(|_| row == 0).map(|(n, _)| n));
+            if let Some((text, _)) = right_chunks.get(row).filter(|(text, _)| !text.is_empty()) {
+                line.push_str(text);
+                content_end = line.len();
+            }
+
+            output.push_str(&line[..line.trim_end_matches(' ').len().max(content_end)]);
+            output.push('\n');
+        }
+    }
+
+    fn push_number(&self, line: &mut String, number: Option<usize>) {
+        if !self.options.line_numbers {
+            return;
+        }
+        match number {
+            Some(n) => {
+                let _ = write!(line, "{:>width$} ", n + 1, width = self.number_width);
+            }
+            None => line.push_str(&" ".repeat(self.number_width + 1)),
+        }
+    }
+
+    /// Split a line into column-width chunks of `(text, display width)`
+    fn chunks(&self, line: &str) -> Vec<(String, usize)> {
+        let mut chunks = Vec::new();
+        let mut text = String::new();
+        let mut width = 0;
+
+        for cell in expand_line(line, self.options.tab_stop) {
+            if width + cell.width > self.column_width && !text.is_empty() {
+                if !self.options.wrap {
+                    // Make room for the ellipsis
+                    while width + 1 > self.column_width {
+                        match text.pop() {
+                            Some(c) => width -= char_width(c),
+                            None => break,
+                        }
+                    }
+                    text.push('…');
+                    width += 1;
+                    chunks.push((text, width));
+                    return chunks;
+                }
+                chunks.push((std::mem::take(&mut text), width));
+                width = 0;
+            }
+            text.push_str(&cell.text);
+            width += cell.width;
+        }
+
+        if !text.is_empty() || chunks.is_empty() {
+            chunks.push((text, width));
+        }
+        chunks
+    }
+}
+
+/// Expand a line into display cells: tabs become spaces up to the next
+/// tab stop, control characters become their Unicode control pictures
+fn expand_line(line: &str, tab_stop: usize) -> Vec<Cell> {
+    let content = line.strip_suffix('\n').unwrap_or(line);
+    let content = content.strip_suffix('\r').unwrap_or(content);
+    let tab_stop = tab_stop.max(1);
+
+    let mut cells = Vec::with_capacity(content.len());
+    let mut column = 0;
+    for c in content.chars() {
+        let cell = match c {
+            '\t' => {
+                let spaces = tab_stop - column % tab_stop;
+                Cell {
+                    text: " ".repeat(spaces),
+                    width: spaces,
+                }
+            }
+            '\0'..='\x1f' => Cell {
+                text: char::from_u32(0x2400 + u32::from(c))
+                    .unwrap_or('?')
+                    .to_string(),
+                width: 1,
+            },
+            '\x7f' => Cell {
+                text: "\u{2421}".to_string(),
+                width: 1,
+            },
+            _ => Cell {
+                text: c.to_string(),
+                width: char_width(c),
+            },
+        };
+        column += cell.width;
+        cells.push(cell);
+    }
+    cells
+}
+
+fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
@@ -0,0 +1,224 @@
+//! Typographic ("smart") punctuation detection and repair
+//!
+//! Model output often uses curly quotes, en/em dashes and ellipses where the
+//! source file has plain ASCII, or the reverse. This module maps typographic
+//! punctuation to ASCII reversibly, and can tell whether a search string
+//! would match once both sides are ASCII-fied, returning the file's exact
+//! spelling as the fixed search string.
+
+use std::ops::Range;
+
+// ============================================================================
+// MAPPING TABLE
+// ============================================================================
+
+/// Typographic punctuation and its ASCII equivalent
+///
+/// Em dashes map to `--`, following the SmartyPants convention (`--` is the
+/// sequence typographic tools turn into an em dash).
+pub const SMART_PUNCTUATION: &[(char, &str)] = &[
+    ('\u{2018}', "'"),   // ‘ left single quotation mark
+    ('\u{2019}', "'"),   // ’ right single quotation mark
+    ('\u{201A}', "'"),   // ‚ single low-9 quotation mark
+    ('\u{201B}', "'"),   // ‛ single high-reversed-9 quotation mark
+    ('\u{2032}', "'"),   // ′ prime
+    ('\u{201C}', "\""),  // “ left double quotation mark
+    ('\u{201D}', "\""),  // ” right double quotation mark
+    ('\u{201E}', "\""),  // „ double low-9 quotation mark
+    ('\u{201F}', "\""),  // ‟ double high-reversed-9 quotation mark
+    ('\u{2033}', "\""),  // ″ double prime
+    ('\u{2010}', "-"),   // ‐ hyphen
+    ('\u{2011}', "-"),   // ‑ non-breaking hyphen
+    ('\u{2012}', "-"),   // ‒ figure dash
+    ('\u{2013}', "-"),   // – en dash
+    ('\u{2212}', "-"),   // − minus sign
+    ('\u{2014}', "--"),  // — em dash
+    ('\u{2015}', "--"),  // ― horizontal bar
+    ('\u{2026}', "..."), // … horizontal ellipsis
+];
+
+/// ASCII equivalent of a typographic punctuation character
+#[must_use]
+pub fn ascii_equivalent(c: char) -> Option<&'static str> {
+    SMART_PUNCTUATION
+        .iter()
+        .find(|(smart, _)| *smart == c)
+        .map(|(_, ascii)| *ascii)
+}
+
+/// Whether `text` contains any typographic punctuation
+#[must_use]
+pub fn has_smart_punctuation(text: &str) -> bool {
+    text.chars().any(|c| ascii_equivalent(c).is_some())
+}
+
+// ============================================================================
+// REVERSIBLE CONVERSION
+// ============================================================================
+
+/// One replaced character in an [`Asciified`] text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    /// Byte range of the ASCII replacement in [`Asciified::text`]
+    pub range: Range<usize>,
+
+    /// The typographic character that was replaced
+    pub original: char,
+}
+
+/// Text with typographic punctuation replaced by ASCII
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Asciified {
+    /// The ASCII-fied text
+    pub text: String,
+
+    /// Replacements made, in order
+    pub substitutions: Vec<Substitution>,
+}
+
+impl Asciified {
+    /// Undo the conversion, restoring the original text exactly
+    #[must_use]
+    pub fn restore(&self) -> String {
+        let mut output = String::with_capacity(self.text.len());
+        let mut last = 0;
+        for sub in &self.substitutions {
+            output.push_str(&self.text[last..sub.range.start]);
+            output.push(sub.original);
+            last = sub.range.end;
+        }
+        output.push_str(&self.text[last..]);
+        output
+    }
+}
+
+/// Replace typographic punctuation in `text` with ASCII, recording each change
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::smart_punctuation::asciify;
+///
+/// let converted = asciify("say(\u{201C}hi\u{201D}) \u{2014} done\u{2026}");
+/// assert_eq!(converted.text, "say(\"hi\") -- done...");
+/// assert_eq!(converted.restore(), "say(\u{201C}hi\u{201D}) \u{2014} done\u{2026}");
+/// ```
+#[must_use]
+pub fn asciify(text: &str) -> Asciified {
+    let mut output = String::with_capacity(text.len());
+    let mut substitutions = Vec::new();
+    for c in text.chars() {
+        match ascii_equivalent(c) {
+            Some(ascii) => {
+                let start = output.len();
+                output.push_str(ascii);
+                substitutions.push(Substitution {
+                    range: start..output.len(),
+                    original: c,
+                });
+            }
+            None => output.push(c),
+        }
+    }
+    Asciified {
+        text: output,
+        substitutions,
+    }
+}
+
+// ============================================================================
+// SEARCH REPAIR
+// ============================================================================
+
+/// A region of a haystack matching a needle once punctuation is ASCII-fied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PunctuationMatch {
+    /// Byte range in the original haystack
+    pub range: Range<usize>,
+
+    /// The haystack's exact text for the range (the fixed search string)
+    pub text: String,
+}
+
+/// Find regions of `haystack` equal to `needle` after ASCII-fying both
+///
+/// Matches are non-overlapping and in order. This answers "would the search
+/// match if smart punctuation were ASCII"; a single match's `text` is the
+/// search string that matches the file exactly.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::smart_punctuation::find_punctuation_insensitive;
+///
+/// let file = "println!(\"it's done\");\n";
+/// let matches = find_punctuation_insensitive(file, "println!(\u{201C}it\u{2019}s done\u{201D});");
+/// assert_eq!(matches.len(), 1);
+/// assert_eq!(matches[0].text, "println!(\"it's done\");");
+/// ```
+#[must_use]
+pub fn find_punctuation_insensitive(haystack: &str, needle: &str) -> Vec<PunctuationMatch> {
+    let needle = asciify(needle).text;
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    // ASCII-fied haystack, with each original character's start offsets
+    let mut converted = String::with_capacity(haystack.len());
+    let mut starts: Vec<(usize, usize)> = Vec::new(); // (converted offset, haystack offset)
+    for (offset, c) in haystack.char_indices() {
+        starts.push((converted.len(), offset));
+        match ascii_equivalent(c) {
+            Some(ascii) => converted.push_str(ascii),
+            None => converted.push(c),
+        }
+    }
+    starts.push((converted.len(), haystack.len()));
+
+    let original_offset = |converted_offset: usize| {
+        starts
+            .binary_search_by_key(&converted_offset, |&(c, _)| c)
+            .ok()
+            .map(|index| starts[index].1)
+    };
+
+    let mut matches: Vec<PunctuationMatch> = Vec::new();
+    for (start, matched) in converted.match_indices(needle.as_str()) {
+        // Skip matches that start or end inside a multi-character replacement
+        let (Some(byte_start), Some(byte_end)) = (
+            original_offset(start),
+            original_offset(start + matched.len()),
+        ) else {
+            continue;
+        };
+        matches.push(PunctuationMatch {
+            range: byte_start..byte_end,
+            text: haystack[byte_start..byte_end].to_string(),
+        });
+    }
+
+    matches
+}
+
+/// The fixed search string, if `needle` matches `haystack` exactly once
+/// after ASCII-fying punctuation and did not match as-is
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::smart_punctuation::fix_search_string;
+///
+/// let file = "let msg = \u{201C}ok\u{201D};";
+/// assert_eq!(fix_search_string(file, "msg = \"ok\""), Some("msg = \u{201C}ok\u{201D}".to_string()));
+/// assert_eq!(fix_search_string(file, "let msg"), None); // already matches
+/// ```
+#[must_use]
+pub fn fix_search_string(haystack: &str, needle: &str) -> Option<String> {
+    if haystack.contains(needle) {
+        return None;
+    }
+    match find_punctuation_insensitive(haystack, needle).as_slice() {
+        [only] => Some(only.text.clone()),
+        _ => None,
+    }
+}
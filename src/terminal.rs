@@ -0,0 +1,261 @@
+//! ANSI/VT escape sequence and terminal control detection
+//!
+//! Text copied from terminal output can carry colour codes (`\x1b[35m`, as
+//! emitted by [`UsageTracker`](crate::usage_tracker::UsageTracker) summaries),
+//! cursor movement, window-title strings, backspaces and carriage-return
+//! overwrites. None of it is in the file. This module parses those
+//! sequences, strips them, and can render overwrites to the visible text.
+
+use crate::line_endings::{LineEndingStyle, analyze_line_endings};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::iter::Peekable;
+use std::ops::{Range, RangeInclusive};
+use std::str::CharIndices;
+
+const ESC: char = '\u{1B}';
+const BEL: char = '\u{07}';
+const BACKSPACE: char = '\u{08}';
+const C1_CSI: char = '\u{9B}';
+
+type Chars<'a> = Peekable<CharIndices<'a>>;
+
+// ============================================================================
+// PUBLIC TYPES
+// ============================================================================
+
+/// Category of terminal control found in text
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalArtefact {
+    /// Select Graphic Rendition: colours and styles (`ESC [ 35 m`)
+    Sgr,
+    /// Other control sequence: cursor movement, erasing (`ESC [ 2 K`)
+    Csi,
+    /// Operating-system command or other control string (`ESC ] 0 ; title BEL`)
+    ControlString,
+    /// Any other escape sequence (`ESC ( B`, `ESC 7`)
+    Escape,
+    /// Backspace (moves the cursor left; the next character overwrites)
+    Backspace,
+    /// Bare carriage return mid-line (the rest of the line overwrites from
+    /// column 0); not reported for classic-Mac `\r` line endings
+    CarriageReturn,
+}
+
+impl TerminalArtefact {
+    /// Short label used in messages
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Sgr => "SGR colour/style code",
+            Self::Csi => "cursor/erase control sequence",
+            Self::ControlString => "OSC/control string",
+            Self::Escape => "escape sequence",
+            Self::Backspace => "backspace",
+            Self::CarriageReturn => "carriage-return overwrite",
+        }
+    }
+}
+
+/// One terminal control in a text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalSequence {
+    pub kind: TerminalArtefact,
+
+    /// Byte range in the text
+    pub range: Range<usize>,
+}
+
+// ============================================================================
+// PARSING
+// ============================================================================
+
+/// Find every escape sequence, backspace and bare carriage return
+///
+/// Sequences follow ECMA-48: CSI is `ESC [` (or the C1 byte U+009B) with
+/// parameter, intermediate and final bytes; control strings (`ESC ]`,
+/// `ESC P`, `ESC X`, `ESC ^`, `ESC _`) run to BEL or `ESC \`. An
+/// unterminated sequence extends to the end of the text.
+///
+/// A bare `\r` only counts as an overwrite when more text follows it on the
+/// same line and the text does not use `\r` line endings.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::terminal::{parse_sequences, TerminalArtefact};
+///
+/// let kinds: Vec<TerminalArtefact> = parse_sequences("\x1b[35mhi\x1b[0m\x1b[2K")
+///     .into_iter()
+///     .map(|s| s.kind)
+///     .collect();
+/// assert_eq!(kinds, [TerminalArtefact::Sgr, TerminalArtefact::Sgr, TerminalArtefact::Csi]);
+/// ```
+#[must_use]
+pub fn parse_sequences(text: &str) -> Vec<TerminalSequence> {
+    let cr_endings = uses_cr_line_endings(text);
+    let mut sequences = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            BACKSPACE => TerminalArtefact::Backspace,
+            '\r' if !cr_endings && chars.peek().is_some_and(|&(_, next)| next != '\n') => {
+                TerminalArtefact::CarriageReturn
+            }
+            C1_CSI => parse_csi(&mut chars),
+            ESC => match chars.peek().map(|&(_, next)| next) {
+                Some('[') => {
+                    chars.next();
+                    parse_csi(&mut chars)
+                }
+                Some(']' | 'P' | 'X' | '^' | '_') => {
+                    chars.next();
+                    skip_control_string(&mut chars);
+                    TerminalArtefact::ControlString
+                }
+                _ => {
+                    // Intermediate bytes, then one final byte
+                    skip_range(&mut chars, '\x20'..='\x2F');
+                    chars.next_if(|&(_, c)| ('\x30'..='\x7E').contains(&c));
+                    TerminalArtefact::Escape
+                }
+            },
+            _ => continue,
+        };
+        let end = chars.peek().map_or(text.len(), |&(offset, _)| offset);
+        sequences.push(TerminalSequence {
+            kind,
+            range: start..end,
+        });
+    }
+    sequences
+}
+
+/// Consume a CSI body (after `ESC [`) and classify it
+fn parse_csi(chars: &mut Chars<'_>) -> TerminalArtefact {
+    skip_range(chars, '\x30'..='\x3F'); // Parameter bytes
+    skip_range(chars, '\x20'..='\x2F'); // Intermediate bytes
+    match chars.next_if(|&(_, c)| ('\x40'..='\x7E').contains(&c)) {
+        Some((_, 'm')) => TerminalArtefact::Sgr,
+        _ => TerminalArtefact::Csi,
+    }
+}
+
+/// Whether bare `\r` is the text's line ending (classic Mac)
+pub(crate) fn uses_cr_line_endings(text: &str) -> bool {
+    let endings = analyze_line_endings(text);
+    endings.cr_count > 0 && endings.style == LineEndingStyle::Cr
+}
+
+fn skip_range(chars: &mut Chars<'_>, range: RangeInclusive<char>) {
+    while chars.next_if(|&(_, c)| range.contains(&c)).is_some() {}
+}
+
+/// Consume a control string up to and including its BEL or `ESC \` terminator
+fn skip_control_string(chars: &mut Chars<'_>) {
+    while let Some((_, c)) = chars.next() {
+        if c == BEL || (c == ESC && chars.next_if(|&(_, next)| next == '\\').is_some()) {
+            return;
+        }
+    }
+}
+
+/// Distinct kinds of terminal control in `text`, in a stable order
+#[must_use]
+pub fn detect_terminal_artefacts(text: &str) -> Vec<TerminalArtefact> {
+    let mut kinds: Vec<TerminalArtefact> = parse_sequences(text)
+        .into_iter()
+        .map(|sequence| sequence.kind)
+        .collect();
+    kinds.sort();
+    kinds.dedup();
+    kinds
+}
+
+// ============================================================================
+// SANITISERS
+// ============================================================================
+
+/// Remove escape sequences, keeping backspaces and carriage returns
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::terminal::strip_ansi;
+///
+/// assert_eq!(strip_ansi("\x1b[1;31merror\x1b[0m: failed"), "error: failed");
+/// ```
+#[must_use]
+pub fn strip_ansi(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+    for sequence in parse_sequences(text) {
+        if matches!(
+            sequence.kind,
+            TerminalArtefact::Backspace | TerminalArtefact::CarriageReturn
+        ) {
+            continue;
+        }
+        output.push_str(&text[last..sequence.range.start]);
+        last = sequence.range.end;
+    }
+    output.push_str(&text[last..]);
+    output
+}
+
+/// Render text the way a terminal would display it
+///
+/// Escape sequences are removed, a backspace moves the cursor one column
+/// left, and a bare carriage return moves it to the start of the line; later
+/// characters overwrite earlier ones. `\r\n` line endings, and `\r` endings
+/// in classic-Mac text, are preserved.
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::terminal::render_terminal;
+///
+/// assert_eq!(render_terminal("Progress 10%\rProgress 100%\n"), "Progress 100%\n");
+/// assert_eq!(render_terminal("teh\x08\x08he"), "the");
+/// assert_eq!(render_terminal("\x1b[32mok\x1b[0m\r\n"), "ok\r\n");
+/// assert_eq!(render_terminal("line one\rtwo\r"), "line one\rtwo\r");
+/// ```
+#[must_use]
+pub fn render_terminal(text: &str) -> String {
+    let stripped = strip_ansi(text);
+    let cr_endings = uses_cr_line_endings(&stripped);
+    let terminator = if cr_endings { '\r' } else { '\n' };
+    let mut output = String::with_capacity(stripped.len());
+    for line in stripped.split_inclusive(terminator) {
+        let (content, ending) = match line.strip_suffix("\r\n") {
+            Some(content) => (content, "\r\n"),
+            None => match line.strip_suffix(terminator) {
+                Some(content) => (content, &line[content.len()..]),
+                None => (line, ""),
+            },
+        };
+
+        let mut cells: Vec<char> = Vec::with_capacity(content.len());
+        let mut cursor: usize = 0;
+        for c in content.chars() {
+            match c {
+                '\r' => cursor = 0,
+                BACKSPACE => cursor = cursor.saturating_sub(1),
+                _ => {
+                    if cursor < cells.len() {
+                        cells[cursor] = c;
+                    } else {
+                        cells.push(c);
+                    }
+                    cursor += 1;
+                }
+            }
+        }
+        output.extend(cells);
+        output.push_str(ending);
+    }
+    output
+}
@@ -0,0 +1,316 @@
+//! Tool-output artefact detection and stripping
+//!
+//! Agents often build search strings by copying from a read tool's output,
+//! bringing along things that are not in the file: line-number gutters
+//! (`  42 | fn main()`, `42→fn main()`, `cat -n` tabs), markdown code fences,
+//! unified-diff `+`/`-` prefixes and truncation markers (`...`, `[truncated]`).
+//! This module recognises those artefacts and removes them.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Longest line number accepted in a gutter (digits)
+const MAX_GUTTER_DIGITS: usize = 7;
+
+/// Lines that stand for omitted content
+const TRUNCATION_MARKERS: &[&str] = &[
+    "...",
+    "\u{2026}",
+    "[...]",
+    "[\u{2026}]",
+    "// ...",
+    "# ...",
+    "/* ... */",
+];
+
+// ============================================================================
+// PUBLIC TYPES
+// ============================================================================
+
+/// A kind of artefact copied from tool output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolArtefact {
+    /// Markdown code fence lines (```` ```rust ````, `~~~`)
+    CodeFence,
+    /// Line numbers before each line (`42 | `, `42→`, `    42\t`)
+    LineNumbers,
+    /// Unified-diff prefixes (`+`, `-`, context space, `@@` headers)
+    DiffPrefix,
+    /// Markers for omitted content (`...`, `…`, `[truncated]`)
+    Truncation,
+}
+
+impl ToolArtefact {
+    /// Short label used in messages
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::CodeFence => "code fence",
+            Self::LineNumbers => "line-number gutter",
+            Self::DiffPrefix => "diff prefixes",
+            Self::Truncation => "truncation marker",
+        }
+    }
+}
+
+// ============================================================================
+// DETECTION
+// ============================================================================
+
+/// Detect tool-output artefacts in `text`, in stripping order
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::tool_artefacts::{detect_artefacts, ToolArtefact};
+///
+/// let pasted = "```rust\n  41 | fn main() {\n  42 |     run();\n```";
+/// assert_eq!(
+///     detect_artefacts(pasted),
+///     [ToolArtefact::CodeFence, ToolArtefact::LineNumbers]
+/// );
+/// assert!(detect_artefacts("fn main() {\n    run();\n}").is_empty());
+/// ```
+#[must_use]
+pub fn detect_artefacts(text: &str) -> Vec<ToolArtefact> {
+    let mut found = Vec::new();
+    if has_code_fence(text) {
+        found.push(ToolArtefact::CodeFence);
+    }
+    if has_line_numbers(text) {
+        found.push(ToolArtefact::LineNumbers);
+    }
+    if has_diff_prefix(text) {
+        found.push(ToolArtefact::DiffPrefix);
+    }
+    if has_truncation(text) {
+        found.push(ToolArtefact::Truncation);
+    }
+    found
+}
+
+/// Whether the first or last non-empty line is a code fence
+fn has_code_fence(text: &str) -> bool {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let first = lines.next();
+    let last = lines.next_back();
+    first.is_some_and(is_fence) || last.is_some_and(is_fence)
+}
+
+/// Whether every content line carries a gutter with consecutive numbers
+fn has_line_numbers(text: &str) -> bool {
+    let gutters: Vec<Option<Gutter>> = content_lines(text).map(parse_gutter).collect();
+    let Some(Some(first)) = gutters.first() else {
+        return false;
+    };
+    // A lone "1: text" or "2024\tTotal" is too ambiguous; a single line
+    // needs a clear separator
+    if gutters.len() == 1 {
+        return matches!(first.separator, '|' | '\u{2192}');
+    }
+    gutters.iter().enumerate().all(|(offset, gutter)| {
+        gutter
+            .as_ref()
+            .is_some_and(|g| g.number == first.number + offset as u64)
+    })
+}
+
+/// Whether every content line starts with a diff prefix
+fn has_diff_prefix(text: &str) -> bool {
+    let lines: Vec<&str> = content_lines(text).collect();
+    let has_header = lines.iter().any(|line| line.starts_with("@@"));
+    let changes: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|line| !is_diff_header(line))
+        .collect();
+
+    if !changes.iter().all(|line| line.starts_with(['+', '-', ' '])) {
+        return false;
+    }
+    let added = changes.iter().any(|line| line.starts_with('+'));
+    let removed = changes.iter().any(|line| line.starts_with('-'));
+    // "- item" lists look like removals; demand stronger evidence for them
+    let bullet_list = !added && changes.iter().all(|line| line.starts_with("- "));
+    (added || removed) && (has_header || added || (changes.len() > 1 && !bullet_list))
+}
+
+/// Whether any line is a truncation marker, or the text ends with `…`
+fn has_truncation(text: &str) -> bool {
+    text.lines().any(is_truncation_marker) || text.trim_end().ends_with('\u{2026}')
+}
+
+// ============================================================================
+// STRIPPING
+// ============================================================================
+
+/// Remove one kind of artefact from `text`
+///
+/// Diff prefixes are removed keeping the new side: when both added and
+/// removed lines are present, removed lines are dropped (tools usually show
+/// diffs of changes already applied to the file).
+#[must_use]
+pub fn strip_artefact(text: &str, artefact: ToolArtefact) -> String {
+    match artefact {
+        ToolArtefact::CodeFence => {
+            let lines: Vec<&str> = text.lines().collect();
+            let first = lines.iter().position(|line| !line.trim().is_empty());
+            let last = lines.iter().rposition(|line| !line.trim().is_empty());
+            map_lines(text, |index, line| {
+                let edge = Some(index) == first || Some(index) == last;
+                (!(edge && is_fence(line))).then(|| line.to_string())
+            })
+        }
+        ToolArtefact::LineNumbers => map_lines(text, |_, line| {
+            Some(match parse_gutter(line) {
+                Some(gutter) => line[gutter.len..].to_string(),
+                None => line.to_string(),
+            })
+        }),
+        ToolArtefact::DiffPrefix => {
+            let both = text
+                .lines()
+                .any(|line| line.starts_with('+') && !is_diff_header(line))
+                && text
+                    .lines()
+                    .any(|line| line.starts_with('-') && !is_diff_header(line));
+            map_lines(text, |_, line| {
+                if is_diff_header(line) || (both && line.starts_with('-')) {
+                    None
+                } else if line.starts_with(['+', '-', ' ']) {
+                    Some(line[1..].to_string())
+                } else {
+                    Some(line.to_string())
+                }
+            })
+        }
+        ToolArtefact::Truncation => {
+            let stripped = map_lines(text, |_, line| {
+                (!is_truncation_marker(line)).then(|| line.to_string())
+            });
+            let content = stripped.trim_end();
+            match content.strip_suffix('\u{2026}') {
+                Some(head) => format!("{head}{}", &stripped[content.len()..]),
+                None => stripped,
+            }
+        }
+    }
+}
+
+/// Remove every detected artefact, producing the clean search string
+///
+/// # Examples
+///
+/// ```
+/// use kodegen_utils::tool_artefacts::strip_artefacts;
+///
+/// assert_eq!(
+///     strip_artefacts("    41\u{2192}fn main() {\n    42\u{2192}    run();"),
+///     "fn main() {\n    run();"
+/// );
+/// assert_eq!(strip_artefacts("@@ -1,2 +1,2 @@\n-old();\n+new();\n keep();"), "new();\nkeep();");
+/// ```
+#[must_use]
+pub fn strip_artefacts(text: &str) -> String {
+    detect_artefacts(text)
+        .into_iter()
+        .fold(text.to_string(), |acc, artefact| {
+            strip_artefact(&acc, artefact)
+        })
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// A parsed line-number gutter
+struct Gutter {
+    number: u64,
+    separator: char,
+    /// Byte length of the gutter, including the padding after the separator
+    len: usize,
+}
+
+/// Parse `  42 | `, `42→`, `    42\t` or `42: ` at the start of a line
+fn parse_gutter(line: &str) -> Option<Gutter> {
+    let digits_start = line.len() - line.trim_start_matches(' ').len();
+    let rest = &line[digits_start..];
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || digits > MAX_GUTTER_DIGITS {
+        return None;
+    }
+    let number = rest[..digits].parse().ok()?;
+
+    let after = &rest[digits..];
+    let padded = after.trim_start_matches(' ');
+    let (separator, tail) = match padded.chars().next()? {
+        '\u{2192}' if after == padded => ('\u{2192}', &padded['\u{2192}'.len_utf8()..]),
+        '\t' if after == padded => ('\t', &padded[1..]),
+        c @ ('|' | ':') => {
+            let tail = &padded[1..];
+            (c, tail.strip_prefix(' ').unwrap_or(tail))
+        }
+        _ => return None,
+    };
+
+    Some(Gutter {
+        number,
+        separator,
+        len: line.len() - tail.len(),
+    })
+}
+
+fn is_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// `@@ ... @@` hunk headers and `+++`/`---` file headers
+fn is_diff_header(line: &str) -> bool {
+    line.starts_with("@@") || line.starts_with("+++ ") || line.starts_with("--- ")
+}
+
+fn is_truncation_marker(line: &str) -> bool {
+    let trimmed = line.trim();
+    TRUNCATION_MARKERS.contains(&trimmed)
+        || (trimmed.starts_with("... (") && trimmed.ends_with(')'))
+        || trimmed.contains("[truncated")
+        || trimmed.contains("(truncated")
+}
+
+/// Lines that carry content: non-empty and not a code fence
+fn content_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty() && !is_fence(line))
+}
+
+/// Rewrite `text` line by line, keeping each line's terminator
+///
+/// `f` receives the line index and content (without terminator) and returns
+/// the new content, or `None` to drop the line together with its terminator.
+fn map_lines(text: &str, mut f: impl FnMut(usize, &str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut last_dropped = false;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let mapped = f(index, content);
+        last_dropped = mapped.is_none();
+        if let Some(mapped) = mapped {
+            output.push_str(&mapped);
+            output.push_str(&line[content.len()..]);
+        }
+    }
+    // Dropping an unterminated last line leaves the previous line's
+    // terminator dangling; remove that one terminator only
+    if last_dropped && !text.ends_with('\n') {
+        let trimmed = output
+            .strip_suffix("\r\n")
+            .or_else(|| output.strip_suffix(['\n', '\r']))
+            .map(str::len);
+        if let Some(len) = trimmed {
+            output.truncate(len);
+        }
+    }
+    output
+}
@@ -0,0 +1,105 @@
+//! Tests for the analysis cache
+
+use kodegen_utils::char_analysis::{
+    AnalysisCache, AnalysisCacheConfig, CachePolicy, CharCodeData, get_analysis_cache,
+};
+
+fn cache(capacity: usize) -> AnalysisCache {
+    AnalysisCache::new(AnalysisCacheConfig {
+        capacity,
+        ..AnalysisCacheConfig::default()
+    })
+}
+
+#[test]
+fn test_hits_and_misses() {
+    let cache = cache(10);
+    let first = cache.analyze("a\tb", "a b", CachePolicy::Use);
+    let second = cache.analyze("a\tb", "a b", CachePolicy::Use);
+    assert_eq!(first.report, second.report);
+
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.bytes, 6);
+    assert!((stats.hit_rate() - 0.5).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_separator_pairs_do_not_collide() {
+    let cache = cache(10);
+    let left = cache.analyze("a|b", "c", CachePolicy::Use);
+    let right = cache.analyze("a", "b|c", CachePolicy::Use);
+    assert_ne!(left.visual_diff_with_codes, right.visual_diff_with_codes);
+    assert_eq!(cache.stats().misses, 2);
+    assert_eq!(cache.stats().entries, 2);
+}
+
+#[test]
+fn test_capacity_and_byte_limits_evict() {
+    let cache = cache(2);
+    for expected in ["a", "b", "c"] {
+        let _ = cache.analyze(expected, "x", CachePolicy::Use);
+    }
+    let stats = cache.stats();
+    assert_eq!((stats.entries, stats.evictions), (2, 1));
+
+    // The oldest entry was evicted
+    let _ = cache.analyze("a", "x", CachePolicy::Use);
+    assert_eq!(cache.stats().hits, 0);
+
+    cache.configure(AnalysisCacheConfig {
+        capacity: 10,
+        max_entry_bytes: Some(8),
+        max_total_bytes: Some(4),
+    });
+    assert_eq!(cache.stats().entries, 2);
+    assert_eq!(cache.stats().bytes, 4);
+
+    // Over the per-entry limit: analyzed, never cached
+    let _ = cache.analyze("too long", "for the cache", CachePolicy::Use);
+    assert_eq!(cache.stats().entries, 2);
+}
+
+#[test]
+fn test_policies_and_clear() {
+    let cache = cache(10);
+    let _ = cache.analyze("a", "b", CachePolicy::Bypass);
+    assert_eq!(cache.stats().entries, 0);
+    assert_eq!(cache.stats().misses, 0);
+
+    let _ = cache.analyze("a", "b", CachePolicy::Refresh);
+    let _ = cache.analyze("a", "b", CachePolicy::Refresh);
+    assert_eq!(cache.stats().entries, 1);
+    assert_eq!(cache.stats().hits, 0);
+
+    cache.clear();
+    assert_eq!(cache.stats().entries, 0);
+    assert_eq!(cache.stats().bytes, 0);
+    cache.reset_stats();
+    assert_eq!(cache.stats().evictions, 0);
+
+    // Capacity 0 disables caching entirely
+    let disabled = self::cache(0);
+    let _ = disabled.analyze("a", "b", CachePolicy::Use);
+    assert_eq!(disabled.stats(), Default::default());
+}
+
+#[test]
+fn test_global_cache_is_used_by_analyze() {
+    let direct = CharCodeData::analyze_with("global\u{00A0}", "global ", CachePolicy::Bypass);
+    let cached = CharCodeData::analyze("global\u{00A0}", "global ");
+    assert_eq!(direct.report, cached.report);
+    assert!(get_analysis_cache().config().capacity > 0);
+}
+
+#[test]
+fn test_line_analysis_bypasses_global_cache() {
+    let lines = CharCodeData::analyze_lines("per-line\u{00A0}a\nsame\n", "per-line a\nsame\n");
+    assert!(lines.lines[0].analysis.is_some());
+
+    // The line pair was not stored, so analysing it again is not a hit
+    let hits = get_analysis_cache().stats().hits;
+    let _ = CharCodeData::analyze("per-line\u{00A0}a", "per-line a");
+    assert_eq!(get_analysis_cache().stats().hits, hits);
+}
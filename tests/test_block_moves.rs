@@ -0,0 +1,44 @@
+//! Tests for block move detection
+
+use kodegen_utils::block_moves::{DEFAULT_MIN_MOVE_CHARS, detect_block_moves, format_with_moves};
+
+const HELPER: &str = "fn helper(input: &str) -> usize {\n    input.trim().len()\n}\n";
+const MAIN: &str = "fn main() {\n    println!(\"{}\", helper(\"  x  \"));\n}\n";
+const LIB: &str = "pub fn lib() {}\n";
+
+#[test]
+fn test_detects_moved_function() {
+    let old = format!("{HELPER}{LIB}{MAIN}");
+    let new = format!("{LIB}{MAIN}{HELPER}");
+
+    let moves = detect_block_moves(&old, &new, DEFAULT_MIN_MOVE_CHARS);
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].from_lines, 0..3);
+    assert_eq!(moves[0].to_lines, 4..7);
+    assert_eq!(moves[0].text, HELPER);
+}
+
+#[test]
+fn test_small_blocks_are_not_moves() {
+    let old = "}\nfoo();\n";
+    let new = "foo();\n}\n";
+    assert!(detect_block_moves(old, new, DEFAULT_MIN_MOVE_CHARS).is_empty());
+}
+
+#[test]
+fn test_format_collapses_moved_block() {
+    let old = format!("{HELPER}{LIB}{MAIN}");
+    let new = format!("{LIB}{MAIN}{HELPER}");
+
+    let rendered = format_with_moves(&old, &new, DEFAULT_MIN_MOVE_CHARS);
+    assert_eq!(
+        rendered,
+        format!("{{~moved to line 5 (3 lines)~}}\n{LIB}{MAIN}{{~moved from line 1 (3 lines)~}}\n")
+    );
+}
+
+#[test]
+fn test_format_without_moves_marks_lines() {
+    let rendered = format_with_moves("a\nb\n", "a\nc\n", DEFAULT_MIN_MOVE_CHARS);
+    assert_eq!(rendered, "a\n{-b-}\n{+c+}\n");
+}
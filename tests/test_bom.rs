@@ -0,0 +1,76 @@
+//! Tests for BOM handling and format fingerprints
+
+use kodegen_utils::bom::{
+    BOM_CHAR, Bom, FormatFingerprint, detect_bom, restore_bom, restore_bom_str, strip_bom,
+    strip_bom_str,
+};
+use kodegen_utils::encoding::TextEncoding;
+use kodegen_utils::line_endings::LineEndingStyle;
+
+#[test]
+fn test_detect_and_strip_every_bom() {
+    for bom in Bom::ALL {
+        let mut bytes = bom.bytes().to_vec();
+        bytes.extend_from_slice(b"x");
+        assert_eq!(detect_bom(&bytes), Some(bom), "{bom}");
+        assert_eq!(strip_bom(&bytes), (Some(bom), &b"x"[..]));
+        assert_eq!(restore_bom(Some(bom), b"x"), bytes);
+        assert_eq!(Bom::for_encoding(bom.encoding()), Some(bom));
+    }
+    assert_eq!(strip_bom(b"plain"), (None, &b"plain"[..]));
+    assert_eq!(Bom::for_encoding(TextEncoding::Latin1), None);
+}
+
+#[test]
+fn test_restore_does_not_duplicate() {
+    assert_eq!(
+        restore_bom(Some(Bom::Utf8), b"\xEF\xBB\xBFx"),
+        b"\xEF\xBB\xBFx"
+    );
+    assert_eq!(restore_bom(None, b"x"), b"x");
+    assert_eq!(restore_bom_str(true, "\u{FEFF}x"), "\u{FEFF}x");
+    assert_eq!(restore_bom_str(false, "x"), "x");
+}
+
+#[test]
+fn test_search_at_line_one_matches_after_strip() {
+    let content = format!("{BOM_CHAR}use std::io;\nfn main() {{}}\n");
+    let search = "use std::io;\nfn main";
+    assert!(!content.starts_with(search));
+
+    let (had_bom, body) = strip_bom_str(&content);
+    assert!(had_bom);
+    assert!(body.starts_with(search));
+    // A BOM only at the start is metadata; a second one is content
+    assert_eq!(strip_bom_str("\u{FEFF}\u{FEFF}x"), (true, "\u{FEFF}x"));
+}
+
+#[test]
+fn test_fingerprint_detection() {
+    let format = FormatFingerprint::detect("\u{FEFF}a\r\nb\r\n");
+    assert!(format.has_bom);
+    assert_eq!(format.line_ending, Some(LineEndingStyle::Crlf));
+    assert!(!format.mixed_line_endings);
+
+    let format = FormatFingerprint::detect("single line");
+    assert!(!format.has_bom);
+    assert_eq!(format.line_ending, None);
+    assert_eq!(format.apply("still\none"), "still\none");
+
+    assert!(FormatFingerprint::detect("a\r\nb\nc").mixed_line_endings);
+}
+
+#[test]
+fn test_fingerprint_round_trip() {
+    let original = "\u{FEFF}one\r\ntwo\r\n";
+    let format = FormatFingerprint::detect(original);
+    assert_eq!(format.apply(format.strip(original)), original);
+
+    // Mixed files keep whatever the edit produced
+    let mixed = FormatFingerprint::detect("a\r\nb\nc");
+    assert_eq!(mixed.apply("x\ny\r\n"), "x\ny\r\n");
+
+    // A file without a BOM does not gain one, and a stray one is dropped
+    let plain = FormatFingerprint::detect("a\nb\n");
+    assert_eq!(plain.apply("\u{FEFF}a\r\nb\r\n"), "a\nb\n");
+}
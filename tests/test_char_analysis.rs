@@ -0,0 +1,396 @@
+//! Tests for character-level analysis
+
+use kodegen_utils::char_analysis::{
+    ANALYSIS_SCHEMA_VERSION, AnalysisJsonError, CharCodeData, EncodingIssue, FindingKind,
+    LineStatus, SecurityIssue, Severity, WhitespaceIssue, normalize_invisible_chars,
+    strip_bidi_controls,
+};
+
+#[test]
+fn test_analyze_bytes_valid_utf8_matches_str_analysis() {
+    let from_bytes = CharCodeData::analyze_bytes(b"let x\t= 1;", b"let x = 1;");
+    let from_str = CharCodeData::analyze("let x\t= 1;", "let x = 1;");
+    assert_eq!(from_bytes.report, from_str.report);
+    assert_eq!(from_bytes.whitespace_issues, from_str.whitespace_issues);
+}
+
+#[test]
+fn test_analyze_bytes_reports_invalid_bytes() {
+    // Latin-1 encoded "é" against its UTF-8 form
+    let analysis = CharCodeData::analyze_bytes(b"caf\xe9 = 1", "café = 1".as_bytes());
+    assert_eq!(
+        analysis.report,
+        "233:1[U+00E9 LATIN SMALL LETTER E WITH ACUTE],0xe9:1[\\xe9]"
+    );
+    assert_eq!(analysis.classification.invalid.len(), 1);
+    assert!(
+        analysis
+            .encoding_issues
+            .contains(&EncodingIssue::InvalidUtf8)
+    );
+    assert!(
+        analysis
+            .visual_diff_with_codes
+            .starts_with("caf{-\\xe9-}{+é+} = 1")
+    );
+}
+
+#[test]
+fn test_analyze_bytes_distinguishes_different_invalid_bytes() {
+    let analysis = CharCodeData::analyze_bytes(b"a\xffb", b"a\xfeb");
+    assert_eq!(analysis.diff_length, 2);
+    assert!(
+        analysis
+            .format_detailed_report()
+            .contains("Invalid UTF-8 bytes")
+    );
+}
+
+#[test]
+fn test_analyze_reports_confusables() {
+    let analysis = CharCodeData::analyze("let v\u{0430}lue = 1;", "let value = 1;");
+    assert_eq!(analysis.confusables.len(), 1);
+    assert_eq!(
+        analysis.confusables[0].describe(),
+        "'а' U+0430 vs 'a' U+0061"
+    );
+    assert!(
+        analysis
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("look-alike")
+    );
+    assert!(
+        analysis
+            .format_detailed_report()
+            .contains("Confusable: 'а' U+0430 vs 'a' U+0061")
+    );
+}
+
+#[test]
+fn test_bidi_controls_in_common_text_are_reported() {
+    // The override sits in the shared prefix, outside the diff
+    let analysis = CharCodeData::analyze(
+        "x = \"\u{202E}txt\u{202C}\"; y = 1;",
+        "x = \"\u{202E}txt\u{202C}\"; y = 2;",
+    );
+    assert_eq!(analysis.security_issues, vec![SecurityIssue::BidiOverride]);
+    assert!(
+        analysis
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .starts_with("SECURITY")
+    );
+}
+
+#[test]
+fn test_directional_marks_are_not_critical() {
+    // RLM in Hebrew and Arabic text, in the shared part and in the diff
+    let shared = CharCodeData::analyze("שלום\u{200F} x = 1", "שלום\u{200F} x = 2");
+    assert_eq!(shared.security_issues, vec![SecurityIssue::BidiMark]);
+    assert!(
+        shared
+            .findings
+            .iter()
+            .all(|finding| finding.severity < Severity::Critical)
+    );
+    assert!(
+        !shared
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .starts_with("SECURITY")
+    );
+
+    let in_diff = CharCodeData::analyze("مرحبا\u{200F}!", "مرحبا!");
+    let top = &in_diff.findings[0];
+    assert_eq!(top.kind, FindingKind::Security(SecurityIssue::BidiMark));
+    assert_eq!(top.severity, Severity::Warning);
+}
+
+#[test]
+fn test_unbalanced_bidi() {
+    let analysis = CharCodeData::analyze("a\u{2066}b\nc", "abc");
+    assert_eq!(
+        analysis.security_issues,
+        vec![SecurityIssue::BidiIsolate, SecurityIssue::UnbalancedBidi]
+    );
+
+    let stray = CharCodeData::analyze("a\u{202C}b", "ab");
+    assert_eq!(stray.security_issues, vec![SecurityIssue::UnbalancedBidi]);
+    assert_eq!(strip_bidi_controls("a\u{2066}b\u{200F}"), "ab");
+}
+
+#[test]
+fn test_exotic_spaces_and_invisibles_are_classified() {
+    let analysis = CharCodeData::analyze("a b\u{00AD}c", "a\u{2003}bc\u{FE0F}");
+    let names = analysis.format_detailed_report();
+    assert!(names.contains("Whitespace: "));
+    assert!(names.contains("U+2003 EM SPACE×1"));
+    assert!(names.contains("U+00AD SOFT HYPHEN (SHY)×1"));
+    assert!(names.contains("U+FE0F VARIATION SELECTOR-16 (VS)×1"));
+    assert!(analysis.classification.unicode.is_empty());
+    assert_eq!(analysis.classification.invisible.len(), 2);
+    assert!(analysis.has_zero_width);
+    assert!(
+        analysis
+            .whitespace_issues
+            .contains(&WhitespaceIssue::ExoticSpaces)
+    );
+}
+
+#[test]
+fn test_normalize_invisible_chars() {
+    assert_eq!(
+        normalize_invisible_chars("\u{3000}x\u{2060}\u{202F}=\u{00AD}\u{0085}"),
+        " x =\n"
+    );
+    assert_eq!(normalize_invisible_chars("plain\ttext\n"), "plain\ttext\n");
+}
+
+#[test]
+fn test_smart_punctuation_diagnosis() {
+    let analysis = CharCodeData::analyze("msg = \u{201C}it\u{2019}s\u{201D}", "msg = \"it's\"");
+    assert_eq!(
+        analysis.smart_punctuation,
+        vec!['\u{201C}', '\u{2019}', '\u{201D}']
+    );
+    assert!(
+        analysis
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("“→\", ’→', ”→\"")
+    );
+
+    // A real edit alongside the quotes is not a punctuation-only diff
+    let mixed = CharCodeData::analyze("a = \u{201C}x\u{201D}", "b = \"y\"");
+    assert!(mixed.smart_punctuation.is_empty());
+}
+
+#[test]
+fn test_json_round_trip() {
+    let analysis = CharCodeData::analyze("x\u{200B} = \u{201C}a\u{201D}", "x = \"a\"\r\n");
+    let json = analysis.to_json();
+    assert_eq!(json["schema_version"], ANALYSIS_SCHEMA_VERSION);
+    assert_eq!(json["analysis"]["has_zero_width"], true);
+
+    let parsed = CharCodeData::from_json(json).unwrap();
+    assert_eq!(parsed.report, analysis.report);
+    assert_eq!(parsed.whitespace_issues, analysis.whitespace_issues);
+    assert_eq!(parsed.smart_punctuation, analysis.smart_punctuation);
+}
+
+#[test]
+fn test_json_without_later_fields_deserializes() {
+    let mut json = CharCodeData::analyze("a\u{200B}", "a").to_json();
+    let analysis = json["analysis"].as_object_mut().unwrap();
+    for field in ["security_issues", "confusables", "smart_punctuation"] {
+        analysis.remove(field);
+    }
+
+    let parsed = CharCodeData::from_json(json).unwrap();
+    assert!(parsed.has_zero_width);
+    assert!(parsed.security_issues.is_empty());
+    assert!(parsed.confusables.is_empty());
+    assert!(parsed.smart_punctuation.is_empty());
+}
+
+#[test]
+fn test_json_version_and_schema() {
+    let mut json = CharCodeData::analyze("a", "b").to_json();
+    json["schema_version"] = (ANALYSIS_SCHEMA_VERSION + 1).into();
+    assert!(matches!(
+        CharCodeData::from_json(json),
+        Err(AnalysisJsonError::UnsupportedVersion { .. })
+    ));
+
+    // Version 1 flags meant something else and cannot be upgraded
+    let mut json = CharCodeData::analyze("a", "b").to_json();
+    json["schema_version"] = 1.into();
+    assert!(matches!(
+        CharCodeData::from_json(json),
+        Err(AnalysisJsonError::UnsupportedVersion {
+            found: 1,
+            supported: ANALYSIS_SCHEMA_VERSION
+        })
+    ));
+
+    let schema = CharCodeData::json_schema();
+    assert!(schema["properties"]["schema_version"].is_object());
+    assert!(schema.to_string().contains("security_issues"));
+}
+
+#[test]
+fn test_unicode_names_in_reports() {
+    let analysis = CharCodeData::analyze("a\u{00A0}b\tc", "a b c");
+    assert_eq!(
+        analysis.report,
+        "9:1[U+0009 CHARACTER TABULATION (TAB)],32:2[ ],98:2[b],160:1[U+00A0 NO-BREAK SPACE (NBSP)]"
+    );
+    let report = analysis.format_detailed_report();
+    assert!(report.contains("U+00A0 NO-BREAK SPACE (NBSP)×1"));
+    assert!(!report.contains("\\xa0"));
+
+    let unassigned = CharCodeData::analyze("x", "\u{0378}");
+    assert!(unassigned.report.contains("888:1[U+0378 <unassigned>]"));
+}
+
+#[test]
+fn test_findings_are_ranked_with_breakdown() {
+    let analysis = CharCodeData::analyze("\tx\u{200B}\u{200B}", "    x");
+    let kinds: Vec<&FindingKind> = analysis.findings.iter().map(|f| &f.kind).collect();
+    assert_eq!(
+        kinds[..2],
+        [
+            &FindingKind::Whitespace(WhitespaceIssue::TabsVsSpaces),
+            &FindingKind::ZeroWidth
+        ]
+    );
+
+    let zero_width = &analysis.findings[1];
+    assert_eq!(zero_width.severity, Severity::Warning);
+    assert_eq!(zero_width.explained_chars, 2);
+    assert!((zero_width.explained - 2.0 / 9.0).abs() < 1e-9);
+
+    let suggestion = analysis.suggestion.as_deref().unwrap();
+    assert!(suggestion.starts_with("Replace tabs with spaces"));
+    assert!(suggestion.contains("tabs vs spaces explains 5/9 of the diff"));
+    assert!(suggestion.contains("zero-width characters explains 2/9 of the diff"));
+    assert!(
+        analysis
+            .format_detailed_report()
+            .contains("1. [Warning] tabs vs spaces")
+    );
+}
+
+#[test]
+fn test_security_findings_outrank_everything() {
+    let analysis = CharCodeData::analyze("a\u{202E}b\tc", "a\u{202E}b c");
+    let top = &analysis.findings[0];
+    assert_eq!(top.kind, FindingKind::Security(SecurityIssue::BidiOverride));
+    assert_eq!(top.severity, Severity::Critical);
+    assert_eq!(top.explained_chars, 0);
+    assert!(
+        analysis
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .starts_with("SECURITY")
+    );
+}
+
+#[test]
+fn test_composed_and_decomposed_detection() {
+    let ascii = CharCodeData::analyze("abc", "abd");
+    assert!(!ascii.unicode_analysis.has_composed);
+    assert!(!ascii.unicode_analysis.has_decomposed);
+
+    let composed = CharCodeData::analyze("caf\u{00E9}", "cafe");
+    assert!(composed.unicode_analysis.has_composed);
+    assert!(!composed.unicode_analysis.has_decomposed);
+
+    let mixed = CharCodeData::analyze("caf\u{00E9}", "cafe\u{0301}");
+    assert!(mixed.unicode_analysis.has_composed);
+    assert!(mixed.unicode_analysis.has_decomposed);
+    assert!(mixed.unicode_analysis.normalization_mismatch);
+    assert!(!mixed.unicode_analysis.compatibility_mismatch);
+}
+
+#[test]
+fn test_compatibility_normalization() {
+    let analysis = CharCodeData::analyze("\u{FF46}n \u{FB01}le\u{00B2}()", "fn file2()");
+    let unicode = &analysis.unicode_analysis;
+    assert!(unicode.compatibility_mismatch);
+    assert!(!unicode.normalization_mismatch);
+
+    let mappings: Vec<(char, &str)> = unicode
+        .compatibility_mappings
+        .iter()
+        .map(|m| (m.from, m.to.as_str()))
+        .collect();
+    assert_eq!(
+        mappings,
+        [('\u{FF46}', "f"), ('\u{FB01}', "fi"), ('\u{00B2}', "2")]
+    );
+    assert_eq!(
+        unicode.compatibility_mappings[1].describe(),
+        "'\u{FB01}' U+FB01 → \"fi\""
+    );
+
+    assert_eq!(
+        analysis.findings[0].kind,
+        FindingKind::CompatibilityNormalization
+    );
+    assert!(
+        analysis
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("(\u{FF46}→f, \u{FB01}→fi, \u{00B2}→2)")
+    );
+    assert!(
+        analysis
+            .format_detailed_report()
+            .contains("Compatibility characters (NFKC)")
+    );
+}
+
+#[test]
+fn test_analyze_lines_statuses() {
+    let search = "fn run() {\r\n    let a = 1;\n\tlet b = 2;\n    let  c = 3;\n    let d = 4;\n}\n";
+    let found = "fn run() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n    let d = 5;\n}\n";
+    let lines = CharCodeData::analyze_lines(search, found);
+
+    let statuses: Vec<LineStatus> = lines.lines.iter().map(|l| l.status).collect();
+    assert_eq!(
+        statuses,
+        [
+            LineStatus::LineEnding,
+            LineStatus::Identical,
+            LineStatus::TabsVsSpaces,
+            LineStatus::WhitespaceOnly,
+            LineStatus::Content,
+            LineStatus::Identical,
+        ]
+    );
+    assert_eq!(
+        lines.summary(),
+        "4 of 6 lines differ: 1 line ending, 1 tabs vs spaces, 1 whitespace only, 1 content"
+    );
+
+    // Differing lines carry their own analysis
+    let tabs = &lines.lines[2];
+    assert_eq!((tabs.expected_line, tabs.actual_line), (Some(3), Some(3)));
+    let analysis = tabs.analysis.as_ref().unwrap();
+    assert!(
+        analysis
+            .whitespace_issues
+            .contains(&WhitespaceIssue::TabsVsSpaces)
+    );
+    assert!(lines.lines[1].analysis.is_none());
+    assert!(lines.format().contains("line 3: tabs vs spaces"));
+}
+
+#[test]
+fn test_analyze_lines_missing_and_extra() {
+    let lines = CharCodeData::analyze_lines("a\nb\nc\n", "a\nx\ny\nc\n");
+    let statuses: Vec<LineStatus> = lines.lines.iter().map(|l| l.status).collect();
+    assert_eq!(
+        statuses,
+        [
+            LineStatus::Identical,
+            LineStatus::Content,
+            LineStatus::Extra,
+            LineStatus::Identical
+        ]
+    );
+    assert_eq!(lines.lines[2].actual_line, Some(3));
+    assert!(lines.format().contains("file line 3: extra in file"));
+
+    let lines = CharCodeData::analyze_lines("a\nb\n", "a\n");
+    assert_eq!(lines.lines[1].status, LineStatus::Missing);
+    assert!(CharCodeData::analyze_lines("a\nb", "a\nb").is_identical());
+}
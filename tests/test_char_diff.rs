@@ -1,6 +1,10 @@
 //! Tests for character-level diff functionality
 
-use kodegen_utils::char_diff::CharDiff;
+use kodegen_utils::char_diff::{
+    CharDiff, DEFAULT_EDIT_COST, DiffKind, DiffOp, DiffStats, HunkKind, classify_change,
+    cleanup_efficiency, cleanup_semantic, cleanup_semantic_lossless, diff_bytes, diff_chars,
+    diff_hunks, format_ops,
+};
 
 #[test]
 fn test_unicode_suffix_no_panic() {
@@ -72,3 +76,191 @@ fn test_completely_different() {
     assert_eq!(diff.expected_part, "abc");
     assert_eq!(diff.actual_part, "xyz");
 }
+
+#[test]
+fn test_diff_chars_minimal() {
+    let ops = diff_chars("cats", "katz");
+    assert_eq!(format_ops(&ops), "{-c-}{+k+}at{-s-}{+z+}");
+}
+
+#[test]
+fn test_diff_chars_multibyte() {
+    let ops = diff_chars("a世b界c", "a界b世c");
+    let expected: String = ops
+        .iter()
+        .filter(|op| op.kind != DiffKind::Insert)
+        .map(|op| op.text.as_str())
+        .collect();
+    let actual: String = ops
+        .iter()
+        .filter(|op| op.kind != DiffKind::Delete)
+        .map(|op| op.text.as_str())
+        .collect();
+    assert_eq!(expected, "a世b界c");
+    assert_eq!(actual, "a界b世c");
+}
+
+#[test]
+fn test_cleanup_semantic_merges_short_equalities() {
+    let mut ops = vec![
+        DiffOp::delete("m"),
+        DiffOp::insert("s"),
+        DiffOp::equal("o"),
+        DiffOp::delete("u"),
+        DiffOp::insert("fa"),
+        DiffOp::equal("s"),
+        DiffOp::delete("e"),
+    ];
+    cleanup_semantic(&mut ops);
+    assert_eq!(ops, vec![DiffOp::delete("mouse"), DiffOp::insert("sofas")]);
+}
+
+#[test]
+fn test_cleanup_semantic_lossless_word_boundary() {
+    let mut ops = vec![
+        DiffOp::equal("The c"),
+        DiffOp::insert("ow and the c"),
+        DiffOp::equal("at."),
+    ];
+    cleanup_semantic_lossless(&mut ops);
+    assert_eq!(
+        ops,
+        vec![
+            DiffOp::equal("The "),
+            DiffOp::insert("cow and the "),
+            DiffOp::equal("cat."),
+        ]
+    );
+}
+
+#[test]
+fn test_cleanup_semantic_lossless_line_boundary() {
+    let mut ops = vec![
+        DiffOp::equal("AAA\r\n\r\nBBB"),
+        DiffOp::insert("\r\nDDD\r\n\r\nBBB"),
+        DiffOp::equal("\r\nEEE"),
+    ];
+    cleanup_semantic_lossless(&mut ops);
+    assert_eq!(
+        ops,
+        vec![
+            DiffOp::equal("AAA\r\n\r\n"),
+            DiffOp::insert("BBB\r\nDDD\r\n\r\n"),
+            DiffOp::equal("BBB\r\nEEE"),
+        ]
+    );
+}
+
+#[test]
+fn test_cleanup_efficiency_merges_interleaved_edits() {
+    let mut ops = diff_chars("cats", "katz");
+    cleanup_efficiency(&mut ops, DEFAULT_EDIT_COST);
+    assert_eq!(format_ops(&ops), "{-cats-}{+katz+}");
+}
+
+#[test]
+fn test_cleanup_efficiency() {
+    let mut ops = vec![
+        DiffOp::delete("ab"),
+        DiffOp::insert("12"),
+        DiffOp::equal("xyz"),
+        DiffOp::delete("cd"),
+        DiffOp::insert("34"),
+    ];
+    cleanup_efficiency(&mut ops, DEFAULT_EDIT_COST);
+    assert_eq!(
+        ops,
+        vec![DiffOp::delete("abxyzcd"), DiffOp::insert("12xyz34")]
+    );
+}
+
+#[test]
+fn test_format_semantic() {
+    let diff = CharDiff::new("fn load_config(path)", "fn read_settings(path)");
+    assert_eq!(
+        diff.format_semantic(),
+        "fn {-lo-}{+re+}ad_{-config-}{+settings+}(path)"
+    );
+}
+
+#[test]
+fn test_diff_hunks_ranges() {
+    let hunks = diff_hunks("a\nb\nc\nd\n", "a\nB\nc\nd\ne\n");
+    assert_eq!(hunks.len(), 2);
+    assert_eq!(hunks[0].old_lines, 1..2);
+    assert_eq!(hunks[0].new_lines, 1..2);
+    assert_eq!(hunks[0].old_text, "b\n");
+    assert_eq!(hunks[0].new_text, "B\n");
+    assert_eq!(hunks[1].old_lines, 4..4);
+    assert_eq!(hunks[1].new_lines, 4..5);
+}
+
+#[test]
+fn test_hunk_classification() {
+    assert_eq!(
+        classify_change("a\r\nb\r\n", "a\nb\n"),
+        HunkKind::LineEnding
+    );
+    assert_eq!(
+        classify_change("let x = 1;  \n", "let x = 1;\n"),
+        HunkKind::TrailingWhitespace
+    );
+    assert_eq!(
+        classify_change("\tif x {\n\t\ty();\n", "    if x {\n        y();\n"),
+        HunkKind::Indentation
+    );
+    assert_eq!(
+        classify_change("call(a, b,\n     c);\n", "call(a,\n     b, c);\n"),
+        HunkKind::Reflow
+    );
+    assert_eq!(classify_change("x = 1\n", "x = 2\n"), HunkKind::Substantive);
+    assert_eq!(classify_change("a b\n", "a  b\n"), HunkKind::Spacing);
+    assert_eq!(classify_change("a\nb", "a\nb\n"), HunkKind::LineEnding);
+    assert_eq!(classify_change("a\r\n", "a"), HunkKind::LineEnding);
+    assert_eq!(classify_change("a\n", "a\n\n"), HunkKind::Reflow);
+    assert!(!HunkKind::Substantive.is_cosmetic());
+    assert!(HunkKind::Reflow.is_cosmetic());
+}
+
+#[test]
+fn test_byte_diff_aligns_valid_runs() {
+    let ops = diff_bytes(b"fn f() { \xff }", b"fn f() { x }");
+    assert_eq!(format_ops(&ops), "fn f() { {-\\xff-}{+x+} }");
+
+    let diff = CharDiff::from_bytes(b"abc\xc3", b"abc");
+    assert_eq!(diff.expected_part, "\\xc3");
+    assert_eq!(diff.actual_part, "");
+}
+
+#[test]
+fn test_diff_stats() {
+    let stats = DiffStats::compute("a\nb\nc\nd\n", "a\nBB\nc\ne\nf\n");
+    assert_eq!(stats.hunks, 2);
+    assert_eq!(stats.lines_modified, 2);
+    assert_eq!(stats.lines_added, 1);
+    assert_eq!(stats.lines_removed, 0);
+    assert_eq!(stats.chars_deleted, 2);
+    assert_eq!(stats.chars_inserted, 5);
+
+    assert!(DiffStats::compute("same\n", "same\n").is_empty());
+    assert_eq!(CharDiff::new("x\ny\n", "x\n").stats().lines_removed, 1);
+}
+
+#[test]
+fn test_cleanup_semantic_lossless_tab_is_not_line_break() {
+    // Only CR and LF count as line breaks; a tab scores like a space
+    let mut ops = vec![
+        DiffOp::equal("Z A. "),
+        DiffOp::insert("B\tA. "),
+        DiffOp::equal("B\tC"),
+    ];
+    cleanup_semantic_lossless(&mut ops);
+    assert_eq!(
+        ops,
+        vec![
+            DiffOp::equal("Z A."),
+            DiffOp::insert(" B\tA."),
+            DiffOp::equal(" B\tC"),
+        ]
+    );
+}
@@ -0,0 +1,79 @@
+//! Tests for TR39 confusable detection
+
+use kodegen_utils::char_analysis::{CharCodeData, FindingKind, WhitespaceIssue};
+use kodegen_utils::confusables::{confusable_pairs, find_confusable, is_confusable};
+
+#[test]
+fn test_is_confusable() {
+    assert!(is_confusable("p\u{0430}yp\u{0430}l", "paypal"));
+    assert!(is_confusable("rn", "m"));
+    assert!(!is_confusable("paypal", "paypal"));
+    assert!(!is_confusable("cat", "cot"));
+}
+
+#[test]
+fn test_pairs_ignore_real_edits() {
+    let pairs = confusable_pairs("x = \u{0441}ount + 1", "y = count + 2");
+    assert_eq!(pairs.len(), 1);
+    assert_eq!(pairs[0].expected, "\u{0441}");
+    assert_eq!(pairs[0].actual, "c");
+}
+
+#[test]
+fn test_pairs_multi_char_prototype() {
+    let pairs = confusable_pairs("let rnode = 1;", "let mode = 1;");
+    assert_eq!(pairs.len(), 1);
+    assert_eq!(pairs[0].expected, "rn");
+    assert_eq!(pairs[0].actual, "m");
+}
+
+#[test]
+fn test_find_confusable_ranges() {
+    let file = "Ѕеt а = 1;\nSet a = 1;\n";
+    let matches = find_confusable(file, "Set a = 1;");
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].text, "Ѕеt а = 1;");
+    assert_eq!(&file[matches[1].range.clone()], "Set a = 1;");
+
+    // Matches must end on a character boundary of the haystack
+    assert!(find_confusable("m", "r").is_empty());
+    let texts: Vec<String> = find_confusable("modern", "rn")
+        .into_iter()
+        .map(|m| m.text)
+        .collect();
+    assert_eq!(texts, ["m", "rn"]);
+    assert!(find_confusable("abc", "").is_empty());
+}
+
+#[test]
+fn test_differences_with_own_diagnosis_are_not_confusables() {
+    let cases = [
+        (
+            "caf\u{e9}",
+            "cafe\u{301}",
+            FindingKind::NormalizationMismatch,
+        ),
+        (
+            "say \u{201C}hi\u{201D}",
+            "say \"hi\"",
+            FindingKind::SmartPunctuation,
+        ),
+        (
+            "x\u{a0}y",
+            "x y",
+            FindingKind::Whitespace(WhitespaceIssue::ExoticSpaces),
+        ),
+    ];
+    for (expected, actual, kind) in cases {
+        let analysis = CharCodeData::analyze(expected, actual);
+        assert!(analysis.confusables.is_empty(), "{expected:?}");
+        assert_eq!(analysis.findings.len(), 1, "{:?}", analysis.findings);
+        assert_eq!(analysis.findings[0].kind, kind);
+        assert!((analysis.findings[0].explained - 1.0).abs() < f64::EPSILON);
+    }
+
+    // Real homoglyphs next to typographic punctuation are still reported
+    let pairs = confusable_pairs("\u{201C}p\u{0430}y\u{201D}", "\"pay\"");
+    assert_eq!(pairs.len(), 1);
+    assert_eq!(pairs[0].expected, "\u{0430}");
+}
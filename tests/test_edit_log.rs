@@ -0,0 +1,83 @@
+//! Tests for edit log diff statistics
+
+use kodegen_utils::char_diff::DiffStats;
+use kodegen_utils::edit_log::{DiffStatsByExtension, EditBlockLogEntry, EditBlockResult};
+
+fn entry(extension: &str, diff_stats: Option<DiffStats>) -> EditBlockLogEntry {
+    let mut entry = EditBlockLogEntry::new("old", extension, EditBlockResult::ExactMatch);
+    entry.diff_stats = diff_stats;
+    entry
+}
+
+#[test]
+fn test_aggregate_by_extension() {
+    let small = DiffStats::compute("a\n", "b\n");
+    let large = DiffStats::compute("a\nb\n", "c\nd\ne\n");
+    let entries = [
+        entry("rs", Some(small)),
+        entry("rs", Some(large)),
+        entry("py", Some(small)),
+        entry("md", None),
+    ];
+
+    let aggregate: DiffStatsByExtension = entries.iter().collect();
+    let rs = aggregate.get("rs").unwrap();
+    assert_eq!(rs.edits, 2);
+    assert_eq!(rs.totals.lines_modified, 3);
+    assert_eq!(rs.totals.lines_added, 1);
+    assert!(aggregate.get("md").is_none());
+    assert_eq!(aggregate.overall().edits, 3);
+    assert_eq!(
+        aggregate.iter().map(|(ext, _)| ext).collect::<Vec<_>>(),
+        ["py", "rs"]
+    );
+}
+
+#[test]
+fn test_tsv_stats_columns() {
+    let line = entry("rs", Some(DiffStats::compute("a\n", "b\nc\n"))).to_tsv();
+    let columns: Vec<&str> = line.split('\t').collect();
+    assert_eq!(columns[columns.len() - 6..], ["1", "0", "1", "3", "1", "1"]);
+
+    let line = entry("rs", None).to_tsv();
+    assert!(line.ends_with("ExactMatch\t\t\t\t\t\t"));
+}
+
+#[test]
+fn test_entry_without_stats_deserializes() {
+    let mut value = serde_json::to_value(entry("rs", None)).unwrap();
+    value.as_object_mut().unwrap().remove("diff_stats");
+    let parsed: EditBlockLogEntry = serde_json::from_value(value).unwrap();
+    assert!(parsed.diff_stats.is_none());
+}
+
+#[test]
+fn test_with_found_populates_stats() {
+    let entry = EditBlockLogEntry::new("a\nb\n", "rs", EditBlockResult::FuzzyMatchAccepted)
+        .with_found("a\nB\nc\n", 0.8);
+    assert_eq!(entry.search_length, 4);
+    assert_eq!(entry.found_length, Some(6));
+    assert_eq!(entry.similarity, Some(0.8));
+    assert_eq!(
+        entry.diff_stats,
+        Some(DiffStats::compute("a\nb\n", "a\nB\nc\n"))
+    );
+}
+
+#[test]
+fn test_tsv_rows_follow_existing_header() {
+    let entry = entry("rs", Some(DiffStats::compute("a\n", "b\n")));
+    let legacy_header = "timestamp\tsearch_text\tfound_text\tsimilarity\texecution_time_ms\t\
+         exact_match_count\texpected_replacements\tfuzzy_threshold\tbelow_threshold\t\
+         diff\tsearch_length\tfound_length\tfile_extension\tcharacter_codes\t\
+         unique_character_count\tdiff_length\tresult\n";
+    let legacy_row = entry.to_tsv_for_header(legacy_header);
+    assert_eq!(legacy_row.split('\t').count(), 17);
+    assert!(legacy_row.ends_with("ExactMatch"));
+
+    let current_header = legacy_header.replace(
+        "\n",
+        "\tlines_added\tlines_removed\tlines_modified\tchars_inserted\tchars_deleted\thunk_count\n",
+    );
+    assert_eq!(entry.to_tsv_for_header(&current_header), entry.to_tsv());
+}
@@ -0,0 +1,107 @@
+//! Tests for encoding detection and transcoding
+
+use kodegen_utils::encoding::{EncodingError, TextEncoding, decode, decode_as, detect_encoding};
+
+#[test]
+fn test_bom_detection() {
+    let cases: [(&[u8], TextEncoding); 5] = [
+        (b"\xEF\xBB\xBFx", TextEncoding::Utf8),
+        (b"\xFF\xFE\x00\x00x\x00\x00\x00", TextEncoding::Utf32Le),
+        (b"\x00\x00\xFE\xFF\x00\x00\x00x", TextEncoding::Utf32Be),
+        (b"\xFF\xFEx\x00", TextEncoding::Utf16Le),
+        (b"\xFE\xFF\x00x", TextEncoding::Utf16Be),
+    ];
+    for (bytes, encoding) in cases {
+        let detection = detect_encoding(bytes);
+        assert_eq!(detection.encoding, encoding);
+        assert!(detection.has_bom);
+        assert_eq!(decode(bytes).text, "x");
+    }
+}
+
+#[test]
+fn test_bomless_utf16() {
+    let bytes: Vec<u8> = "fn main() {}"
+        .encode_utf16()
+        .flat_map(u16::to_be_bytes)
+        .collect();
+    let decoded = decode(&bytes);
+    assert_eq!(decoded.encoding, TextEncoding::Utf16Be);
+    assert!(!decoded.has_bom);
+    assert_eq!(decoded.text, "fn main() {}");
+}
+
+#[test]
+fn test_legacy_heuristics() {
+    // "中文注释" in GBK
+    let gbk = b"// \xd6\xd0\xce\xc4\xd7\xa2\xca\xcd\n";
+    let decoded = decode(gbk);
+    assert_eq!(decoded.encoding, TextEncoding::Gbk);
+    assert_eq!(decoded.text, "// 中文注释\n");
+
+    // "テスト" in Shift_JIS
+    let sjis = b"s = \"\x83\x65\x83\x58\x83\x67\"";
+    assert_eq!(detect_encoding(sjis).encoding, TextEncoding::ShiftJis);
+
+    // Latin-1 text that happens to form valid double-byte sequences
+    assert_eq!(detect_encoding(b"na\xefve").encoding, TextEncoding::Latin1);
+    assert_eq!(decode(b"na\xefve").text, "naïve");
+    assert!(detect_encoding(b"na\xefve").confidence < 1.0);
+}
+
+#[test]
+fn test_round_trip_preserves_encoding_and_bom() {
+    let original: Vec<u8> = b"\xFF\xFE"
+        .iter()
+        .copied()
+        .chain("let é = 1;\n".encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    let decoded = decode(&original);
+    assert_eq!(decoded.encoding, TextEncoding::Utf16Le);
+    assert_eq!(decoded.encode(&decoded.text).unwrap(), original);
+
+    let latin = decode_as(b"caf\xe9", TextEncoding::Latin1);
+    assert_eq!(latin.encode("caf\u{e9}s").unwrap(), b"caf\xe9s");
+
+    let cp1252 = decode(b"\x93hi\x94 \x80");
+    assert_eq!(cp1252.encoding, TextEncoding::Windows1252);
+    assert_eq!(cp1252.text, "\u{201C}hi\u{201D} \u{20AC}");
+    assert_eq!(cp1252.encode(&cp1252.text).unwrap(), b"\x93hi\x94 \x80");
+}
+
+#[test]
+fn test_unmappable_characters() {
+    let latin = decode_as(b"abc", TextEncoding::Latin1);
+    let error = latin.encode("ab\u{2014}").unwrap_err();
+    assert_eq!(
+        error,
+        EncodingError::Unmappable {
+            character: '\u{2014}',
+            encoding: TextEncoding::Latin1
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "'\u{2014}' (U+2014) cannot be encoded in ISO-8859-1"
+    );
+
+    let sjis = decode_as(b"a", TextEncoding::ShiftJis);
+    assert!(matches!(
+        sjis.encode("a\u{1F600}"),
+        Err(EncodingError::Unmappable {
+            character: '\u{1F600}',
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_malformed_input_is_flagged() {
+    let decoded = decode_as(b"\x00a\x00", TextEncoding::Utf16Be);
+    assert!(decoded.had_errors);
+    assert_eq!(decoded.text, "a\u{FFFD}");
+
+    let decoded = decode_as(b"\xFF\xFE\x00\xD8a\x00", TextEncoding::Utf16Le);
+    assert!(decoded.had_errors);
+    assert!(!decode(b"plain ascii").had_errors);
+}
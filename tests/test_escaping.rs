@@ -0,0 +1,99 @@
+//! Tests for escaping-mismatch detection
+
+use kodegen_utils::char_analysis::{CharCodeData, FindingKind};
+use kodegen_utils::escaping::{
+    EscapeDirection, EscapeScheme, detect_escape_mismatch, diagnose_escaping,
+};
+
+#[test]
+fn test_unescape_schemes() {
+    assert_eq!(
+        EscapeScheme::Json.unescape(r#"a\n\"b\"é😀"#).as_deref(),
+        Some("a\n\"b\"é😀")
+    );
+    assert_eq!(
+        EscapeScheme::CStyle
+            .unescape(r"tab\there\x41\u{1F600}\0")
+            .as_deref(),
+        Some("tab\thereA😀\0")
+    );
+    assert_eq!(
+        EscapeScheme::HtmlEntities
+            .unescape("&lt;a href=&quot;x&quot;&gt; &#39;&#x41;")
+            .as_deref(),
+        Some("<a href=\"x\"> 'A")
+    );
+    assert_eq!(
+        EscapeScheme::Url.unescape("caf%C3%A9%20bar").as_deref(),
+        Some("café bar")
+    );
+
+    // Nothing to decode, or malformed escapes
+    assert_eq!(EscapeScheme::Json.unescape("plain"), None);
+    assert_eq!(EscapeScheme::Json.unescape(r"bad \q escape"), None);
+    assert_eq!(EscapeScheme::HtmlEntities.unescape("a && b"), None);
+    assert_eq!(EscapeScheme::Url.unescape("100%"), None);
+}
+
+#[test]
+fn test_escape_round_trips() {
+    let text = "say \"hi\"\n\t<b> & 'x' é";
+    for scheme in EscapeScheme::ALL {
+        let escaped = scheme.escape(text);
+        assert_ne!(escaped, text, "{scheme:?}");
+        assert_eq!(
+            scheme.unescape(&escaped).as_deref(),
+            Some(text),
+            "{scheme:?}"
+        );
+    }
+}
+
+#[test]
+fn test_diagnose_double_escaped_json() {
+    let mismatch = diagnose_escaping(r#"print(\\\"hi\\\")\\n"#, "print(\"hi\")\n").unwrap();
+    assert_eq!(mismatch.scheme, EscapeScheme::Json);
+    assert_eq!(mismatch.direction, EscapeDirection::Unescape);
+    assert_eq!(mismatch.levels, 2);
+    assert!(mismatch.description().contains("JSON-escaped twice"));
+}
+
+#[test]
+fn test_diagnose_under_escaped() {
+    let mismatch = diagnose_escaping("println!(\"a\nb\");", r#"println!(\"a\nb\");"#).unwrap();
+    assert_eq!(mismatch.direction, EscapeDirection::Escape);
+    assert_eq!(mismatch.levels, 1);
+
+    assert!(diagnose_escaping("same", "same").is_none());
+    assert!(diagnose_escaping("a\\nb", "a b").is_none());
+}
+
+#[test]
+fn test_detect_in_file() {
+    let file = "fn main() {\n    println!(\"hello\");\n}\n";
+    let mismatch =
+        detect_escape_mismatch(r#"fn main() {\n    println!(\"hello\");"#, file).unwrap();
+    assert_eq!(mismatch.candidate, "fn main() {\n    println!(\"hello\");");
+
+    // Already matches, or no unique match
+    assert!(detect_escape_mismatch("println!", file).is_none());
+    assert!(detect_escape_mismatch(r"\n", "a\nb\nc").is_none());
+}
+
+#[test]
+fn test_analysis_reports_escaping() {
+    let analysis = CharCodeData::analyze(r"let s = 1;\nlet t = 2;", "let s = 1;\nlet t = 2;");
+    assert_eq!(
+        analysis.escaping.as_ref().unwrap().scheme,
+        EscapeScheme::Json
+    );
+    assert_eq!(analysis.findings[0].kind, FindingKind::Escaping);
+    assert!(
+        analysis
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .starts_with("Escaping differs: search string is JSON-escaped")
+    );
+    assert!(analysis.format_detailed_report().contains("Escaping:"));
+}
@@ -0,0 +1,61 @@
+//! Tests for whole-file hygiene scanning
+
+use kodegen_utils::hygiene::{HygieneIssueKind, scan_file};
+
+#[test]
+fn test_clean_file() {
+    let report = scan_file("\u{FEFF}fn main() {\n    run();\n}\n");
+    assert!(report.is_clean());
+    assert_eq!(report.line_count, 3);
+    assert_eq!(report.summary(), "No issues in 3 lines");
+}
+
+#[test]
+fn test_character_issues_with_locations() {
+    let content = "a\u{202E}b\nc\u{FEFF}d\u{FFFD}\n\u{7}\u{2060}\n";
+    let report = scan_file(content);
+    let found: Vec<_> = report
+        .issues
+        .iter()
+        .map(|i| (i.kind, i.line, i.column))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (HygieneIssueKind::BidiControl, 1, 2),
+            (HygieneIssueKind::MisplacedBom, 2, 2),
+            (HygieneIssueKind::ReplacementChar, 2, 4),
+            (HygieneIssueKind::ControlChar, 3, 1),
+            (HygieneIssueKind::ZeroWidth, 3, 2),
+        ]
+    );
+    assert_eq!(
+        report.issues[0].message,
+        "U+202E RIGHT-TO-LEFT OVERRIDE (RLO): bidirectional control character"
+    );
+    assert_eq!(report.issues[4].code, Some(0x2060));
+}
+
+#[test]
+fn test_line_ending_and_whitespace_issues() {
+    let report = scan_file("one\r\ntwo \r\nthree\n \tfour\r\n");
+    assert_eq!(report.count(HygieneIssueKind::MixedLineEndings), 1);
+    assert_eq!(report.count(HygieneIssueKind::TrailingWhitespace), 1);
+    assert_eq!(report.count(HygieneIssueKind::MixedIndentation), 1);
+
+    let ending = report
+        .issues
+        .iter()
+        .find(|i| i.kind == HygieneIssueKind::MixedLineEndings)
+        .unwrap();
+    assert_eq!((ending.line, ending.column), (3, 6));
+    assert_eq!(
+        report.summary(),
+        "3 issues in 4 lines: 1 trailing whitespace, 1 mixed line endings, 1 mixed indentation"
+    );
+    assert!(
+        report
+            .format()
+            .contains("  2:4: 1 trailing whitespace character\n")
+    );
+}
@@ -0,0 +1,73 @@
+//! Tests for three-way merge
+
+use kodegen_utils::merge::{ConflictStyle, MergeOptions, merge3, merge3_with_options};
+
+const BASE: &str = "fn main() {\n    let x = 1;\n    let y = 2;\n    println!(\"{x} {y}\");\n}\n";
+
+#[test]
+fn test_non_overlapping_changes_merge_cleanly() {
+    let ours = BASE.replace("let x = 1;", "let x = 10;");
+    let theirs = BASE.replace("println!(\"{x} {y}\");", "println!(\"{x}, {y}\");");
+
+    let result = merge3(BASE, &ours, &theirs);
+    assert!(result.is_clean());
+    assert_eq!(
+        result.merged,
+        "fn main() {\n    let x = 10;\n    let y = 2;\n    println!(\"{x}, {y}\");\n}\n"
+    );
+}
+
+#[test]
+fn test_identical_changes_merge_cleanly() {
+    let ours = BASE.replace("let y = 2;", "let y = 3;");
+    let result = merge3(BASE, &ours, &ours);
+    assert!(result.is_clean());
+    assert_eq!(result.merged, ours);
+}
+
+#[test]
+fn test_insertions_at_different_points() {
+    let ours = format!("// header\n{BASE}");
+    let theirs = format!("{BASE}// footer\n");
+    let result = merge3(BASE, &ours, &theirs);
+    assert!(result.is_clean());
+    assert_eq!(result.merged, format!("// header\n{BASE}// footer\n"));
+}
+
+#[test]
+fn test_overlapping_changes_conflict() {
+    let ours = BASE.replace("let y = 2;", "let y = 3;");
+    let theirs = BASE.replace("let y = 2;", "let y = 4;");
+
+    let result = merge3(BASE, &ours, &theirs);
+    assert!(!result.is_clean());
+    assert_eq!(result.conflicts.len(), 1);
+
+    let conflict = &result.conflicts[0];
+    assert_eq!(conflict.base_lines, 2..3);
+    assert_eq!(conflict.ours, "    let y = 3;\n");
+    assert_eq!(conflict.theirs, "    let y = 4;\n");
+    assert_eq!(conflict.merged_line, 2);
+
+    assert_eq!(
+        result.merged,
+        "fn main() {\n    let x = 1;\n<<<<<<< ours\n    let y = 3;\n=======\n    let y = 4;\n>>>>>>> theirs\n    println!(\"{x} {y}\");\n}\n"
+    );
+}
+
+#[test]
+fn test_diff3_style_includes_base() {
+    let ours = BASE.replace("let y = 2;", "let y = 3;");
+    let theirs = BASE.replace("let y = 2;", "let y = 4;");
+    let options = MergeOptions {
+        ours_label: "agent".to_string(),
+        theirs_label: "human".to_string(),
+        style: ConflictStyle::Diff3,
+        ..MergeOptions::default()
+    };
+
+    let result = merge3_with_options(BASE, &ours, &theirs, &options);
+    assert!(result.merged.contains(
+        "<<<<<<< agent\n    let y = 3;\n||||||| base\n    let y = 2;\n=======\n    let y = 4;\n>>>>>>> human\n"
+    ));
+}
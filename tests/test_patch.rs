@@ -0,0 +1,118 @@
+//! Tests for serialisable patches
+
+use kodegen_utils::patch::{Patch, PatchError, PatchOp};
+
+#[test]
+fn test_apply_roundtrip() {
+    let original = "fn main() {\n    println!(\"hello\");\n}\n";
+    let modified = "fn main() {\n    println!(\"hello, world\");\n    run();\n}\n";
+    let patch = Patch::new(original, modified);
+    assert_eq!(patch.apply(original).unwrap(), modified);
+}
+
+#[test]
+fn test_invert_undoes_patch() {
+    let original = "café → 世界";
+    let modified = "cafe -> 世界!";
+    let patch = Patch::new(original, modified);
+    assert_eq!(patch.invert().apply(modified).unwrap(), original);
+    assert_eq!(patch.invert().invert(), patch);
+}
+
+#[test]
+fn test_compose_matches_sequential_apply() {
+    let v1 = "let x = 1;";
+    let v2 = "let count = 1;";
+    let v3 = "let count = 42; // answer";
+    let first = Patch::new(v1, v2);
+    let second = Patch::new(v2, v3);
+    let composed = first.compose(&second).unwrap();
+    assert_eq!(composed.apply(v1).unwrap(), v3);
+    assert_eq!(composed.invert().apply(v3).unwrap(), v1);
+}
+
+#[test]
+fn test_apply_rejects_wrong_base() {
+    let patch = Patch::new("abc", "abd");
+    assert_eq!(
+        patch.apply("abcd"),
+        Err(PatchError::LengthMismatch {
+            expected: 3,
+            actual: 4
+        })
+    );
+    assert!(matches!(
+        patch.apply("xyz"),
+        Err(PatchError::DeletedTextMismatch { offset: 2, .. })
+    ));
+}
+
+#[test]
+fn test_compose_rejects_unrelated_patch() {
+    let first = Patch::new("abc", "abcd");
+    let second = Patch::new("xy", "xyz");
+    assert!(matches!(
+        first.compose(&second),
+        Err(PatchError::LengthMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_serde_roundtrip() {
+    let patch = Patch::new("hello world", "hello there");
+    let json = serde_json::to_string(&patch).unwrap();
+    assert_eq!(
+        json,
+        r#"{"ops":[{"retain":6},{"delete":"world"},{"insert":"there"}]}"#
+    );
+    let parsed: Patch = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, patch);
+    assert_eq!(
+        parsed.ops(),
+        &[
+            PatchOp::Retain(6),
+            PatchOp::Delete("world".to_string()),
+            PatchOp::Insert("there".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_deserialize_normalises_ops() {
+    let parsed: Patch = serde_json::from_str(r#"{"ops":[{"retain":0}]}"#).unwrap();
+    assert_eq!(parsed, Patch::default());
+    assert_eq!(Patch::default().compose(&parsed).unwrap(), Patch::default());
+
+    let parsed: Patch = serde_json::from_str(
+        r#"{"ops":[{"retain":2},{"insert":""},{"retain":1},{"delete":"x"},{"delete":""}]}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        parsed.ops(),
+        &[PatchOp::Retain(3), PatchOp::Delete("x".to_string())]
+    );
+    assert_eq!(parsed.apply("abcx").unwrap(), "abc");
+}
+
+#[test]
+fn test_deserialize_rejects_length_overflow() {
+    let max = usize::MAX;
+    let json = format!(r#"{{"ops":[{{"retain":{max}}},{{"retain":{max}}}]}}"#);
+    let error = serde_json::from_str::<Patch>(&json).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains(&PatchError::LengthOverflow.to_string())
+    );
+
+    let json = format!(r#"{{"ops":[{{"retain":{max}}},{{"insert":"x"}}]}}"#);
+    assert!(serde_json::from_str::<Patch>(&json).is_err());
+
+    // A single maximal retain is representable and fails the length check
+    let json = format!(r#"{{"ops":[{{"retain":{max}}}]}}"#);
+    let parsed: Patch = serde_json::from_str(&json).unwrap();
+    assert!(matches!(
+        parsed.apply("abc"),
+        Err(PatchError::LengthMismatch { actual: 3, .. })
+    ));
+}
@@ -0,0 +1,126 @@
+//! Tests for the search repair engine
+
+use kodegen_utils::line_endings::LineEndingStyle;
+use kodegen_utils::repair::{RepairTransform, repair_search};
+
+#[test]
+fn test_exact_match_needs_no_repair() {
+    let repair = repair_search("b", "abc").unwrap();
+    assert!(repair.transforms.is_empty());
+    assert_eq!(repair.offset, 1);
+    assert_eq!(repair.description(), "no repair needed");
+}
+
+#[test]
+fn test_strip_zero_width() {
+    let file = "let total = count + 1;\n";
+    let repair = repair_search("total\u{200B} = count", file).unwrap();
+    assert_eq!(repair.transforms, vec![RepairTransform::StripInvisible]);
+    assert_eq!(repair.search, "total = count");
+    assert_eq!(
+        repair.apply("total\u{200B} = count * 2"),
+        "total = count * 2"
+    );
+}
+
+#[test]
+fn test_nfc_and_line_endings() {
+    let file = "caf\u{e9}\r\nbar\r\n";
+    let repair = repair_search("cafe\u{301}\nbar", file).unwrap();
+    assert_eq!(
+        repair.transforms,
+        vec![
+            RepairTransform::LineEndings(LineEndingStyle::Crlf),
+            RepairTransform::Nfc
+        ]
+    );
+    assert_eq!(repair.search, "caf\u{e9}\r\nbar");
+}
+
+#[test]
+fn test_spaces_to_tabs_uses_inferred_width() {
+    // The aligned comment gives the file a 3-space indent unit
+    let file = "fn f() {\n\tif x {\n\t\ty();\n\t}\n}\n/*\n   aligned\n*/\n";
+    let repair = repair_search("   if x {\n      y();", file).unwrap();
+    assert_eq!(
+        repair.transforms,
+        vec![RepairTransform::SpacesToTabs { width: 3 }]
+    );
+    assert_eq!(repair.apply("   z();\n"), "\tz();\n");
+}
+
+#[test]
+fn test_spaces_to_tabs_falls_back_to_common_widths() {
+    let file = "fn f() {\n\tif x {\n\t\ty();\n\t}\n}\n";
+    let repair = repair_search("  if x {\n    y();", file).unwrap();
+    assert_eq!(
+        repair.transforms,
+        vec![RepairTransform::SpacesToTabs { width: 2 }]
+    );
+    assert_eq!(repair.apply("  z();\n"), "\tz();\n");
+}
+
+#[test]
+fn test_ambiguous_or_unfixable_is_rejected() {
+    // Two candidate locations: not a verified repair
+    assert!(repair_search("x\u{200B}y", "xy xy").is_none());
+    // A real difference cannot be repaired
+    assert!(repair_search("let a = 1;", "let b = 2;").is_none());
+}
+
+#[test]
+fn test_repair_compatibility_characters() {
+    let file = "let file = \"x\";\n";
+    let repair = repair_search("let \u{FB01}le = \"\u{FF58}\";", file).unwrap();
+    assert_eq!(repair.transforms, [RepairTransform::Nfkc]);
+    assert_eq!(repair.search, "let file = \"x\";");
+}
+
+#[test]
+fn test_restore_file_smart_punctuation() {
+    let file = "He said \u{201C}don\u{2019}t\u{201D} \u{2014} twice.\n";
+    let repair = repair_search("said \"don't\" -- twice", file).unwrap();
+    assert_eq!(
+        repair.transforms,
+        [RepairTransform::SmartenPunctuation {
+            double_quotes: true,
+            single_quotes: true
+        }]
+    );
+    assert_eq!(
+        repair.search,
+        "said \u{201C}don\u{2019}t\u{201D} \u{2014} twice"
+    );
+    // Dashes in new text may be code, so only quotes are curled
+    assert_eq!(
+        repair.apply("said \"won't\" -- once"),
+        "said \u{201C}won\u{2019}t\u{201D} -- once"
+    );
+
+    // Only the quote kinds the match used are curled
+    let file = "let s = \u{201C}a\u{201D};\n";
+    let repair = repair_search("let s = \"a\";", file).unwrap();
+    assert_eq!(repair.apply("\"it's\""), "\u{201C}it's\u{201D}");
+}
+
+#[test]
+fn test_smart_punctuation_combines_with_other_repairs() {
+    let file = "fn f() {\n    say(\u{201C}hi\u{201D});\n}\n";
+    let repair = repair_search("\tsay(\"hi\");", file).unwrap();
+    assert_eq!(
+        repair.transforms,
+        [
+            RepairTransform::TabsToSpaces { width: 4 },
+            RepairTransform::SmartenPunctuation {
+                double_quotes: true,
+                single_quotes: false
+            }
+        ]
+    );
+    assert_eq!(repair.search, "    say(\u{201C}hi\u{201D});");
+
+    let file = "a = \u{2018}x\u{2019}\r\nb = 1\r\n";
+    let repair = repair_search("a = 'x'\nb = 1", file).unwrap();
+    assert_eq!(repair.search, "a = \u{2018}x\u{2019}\r\nb = 1");
+    assert_eq!(repair.apply("c = 'y'\n"), "c = \u{2018}y\u{2019}\r\n");
+}
@@ -0,0 +1,74 @@
+//! Tests for side-by-side diff rendering
+
+use kodegen_utils::side_by_side::{SideBySideOptions, display_width, render_side_by_side};
+
+fn options(width: usize) -> SideBySideOptions {
+    SideBySideOptions {
+        width,
+        line_numbers: false,
+        ..SideBySideOptions::default()
+    }
+}
+
+#[test]
+fn test_display_width() {
+    assert_eq!(display_width("abc", 4), 3);
+    assert_eq!(display_width("世界", 4), 4);
+    assert_eq!(display_width("🎉!", 4), 3);
+    assert_eq!(display_width("\tx", 4), 5);
+    assert_eq!(display_width("ab\tx", 4), 5);
+    assert_eq!(display_width("ab\tx", 8), 9);
+    assert_eq!(display_width("e\u{301}", 4), 1);
+}
+
+#[test]
+fn test_aligns_unchanged_and_marks_changes() {
+    let output = render_side_by_side("a\nb\nc\n", "a\nB\nc\nd\n", &options(23));
+    assert_eq!(
+        output,
+        "a            a\nb          | B\nc            c\n           > d\n"
+    );
+}
+
+#[test]
+fn test_left_only_lines() {
+    let output = render_side_by_side("a\nb\n", "a\n", &options(23));
+    assert_eq!(output, "a            a\nb          <\n");
+}
+
+#[test]
+fn test_wraps_wide_characters_without_splitting() {
+    // Column width 5: "世界世" is 6 columns wide and must wrap after two characters
+    let output = render_side_by_side("世界世\n", "abc\n", &options(13));
+    assert_eq!(output, "世界  | abc\n世    |\n");
+}
+
+#[test]
+fn test_truncates_when_wrap_disabled() {
+    let options = SideBySideOptions {
+        wrap: false,
+        ..options(13)
+    };
+    let output = render_side_by_side("abcdefgh\n", "x\n", &options);
+    assert_eq!(output, "abcd… | x\n");
+}
+
+#[test]
+fn test_line_numbers() {
+    let options = SideBySideOptions {
+        width: 27,
+        ..SideBySideOptions::default()
+    };
+    let output = render_side_by_side("a\nb\n", "a\nc\n", &options);
+    assert_eq!(output, "1 a            1 a\n2 b          | 2 c\n");
+}
+
+#[test]
+fn test_trailing_whitespace_change_is_visible() {
+    let output = render_side_by_side("let x = 1;\n", "let x = 1;  \n", &options(31));
+    assert_eq!(output, "let x = 1;     | let x = 1;  \n");
+
+    // Padding alone is still trimmed
+    let output = render_side_by_side("a \nb\n", "a \n", &options(23));
+    assert_eq!(output, "a            a \nb          <\n");
+}
@@ -0,0 +1,49 @@
+//! Tests for smart punctuation detection and repair
+
+use kodegen_utils::smart_punctuation::{
+    asciify, find_punctuation_insensitive, fix_search_string, has_smart_punctuation,
+};
+
+#[test]
+fn test_asciify_round_trip() {
+    let original = "\u{2018}a\u{2019} \u{2013} b\u{2026} \u{2014}flag \u{201E}c\u{201F}";
+    let converted = asciify(original);
+    assert_eq!(converted.text, "'a' - b... --flag \"c\"");
+    assert_eq!(converted.substitutions.len(), 7);
+    assert_eq!(converted.restore(), original);
+    assert!(has_smart_punctuation(original));
+    assert!(!has_smart_punctuation(&converted.text));
+}
+
+#[test]
+fn test_fix_search_both_directions() {
+    // Search has smart quotes, file has ASCII
+    let file = "echo \"done\" -- ok\n";
+    assert_eq!(
+        fix_search_string(file, "echo \u{201C}done\u{201D} \u{2014} ok").as_deref(),
+        Some("echo \"done\" -- ok")
+    );
+
+    // File has an em dash, search has ASCII
+    let file = "// wait\u{2014}really?\n";
+    assert_eq!(
+        fix_search_string(file, "wait--really").as_deref(),
+        Some("wait\u{2014}really")
+    );
+}
+
+#[test]
+fn test_match_must_not_split_replacement() {
+    // A single "-" cannot match half of an em dash's "--"
+    assert!(find_punctuation_insensitive("a\u{2014}b", "a-").is_empty());
+    assert_eq!(find_punctuation_insensitive("x - y - z", "-").len(), 2);
+    // Ambiguous matches do not produce a fix
+    assert_eq!(
+        fix_search_string("'a' and \u{2018}a\u{2019}", "\"a\""),
+        None
+    );
+    assert_eq!(
+        fix_search_string("'a' and \u{2018}a\u{2019}", "\u{2018}a'"),
+        None
+    );
+}
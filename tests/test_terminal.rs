@@ -0,0 +1,94 @@
+//! Tests for terminal escape sequence detection and rendering
+
+use kodegen_utils::char_analysis::{CharCodeData, FindingKind, WhitespaceIssue};
+use kodegen_utils::terminal::{
+    TerminalArtefact, detect_terminal_artefacts, parse_sequences, render_terminal, strip_ansi,
+};
+
+#[test]
+fn test_parse_sequence_ranges() {
+    let text = "a\x1b[38;5;208mb\x1b]0;title\x07c\x1b]8;;http://x\x1b\\d\x1b(Be\u{9B}2Jf";
+    let parsed: Vec<(TerminalArtefact, &str)> = parse_sequences(text)
+        .into_iter()
+        .map(|s| (s.kind, &text[s.range]))
+        .collect();
+    assert_eq!(
+        parsed,
+        [
+            (TerminalArtefact::Sgr, "\x1b[38;5;208m"),
+            (TerminalArtefact::ControlString, "\x1b]0;title\x07"),
+            (TerminalArtefact::ControlString, "\x1b]8;;http://x\x1b\\"),
+            (TerminalArtefact::Escape, "\x1b(B"),
+            (TerminalArtefact::Csi, "\u{9B}2J"),
+        ]
+    );
+    assert_eq!(strip_ansi(text), "abcdef");
+}
+
+#[test]
+fn test_detect_kinds() {
+    assert_eq!(
+        detect_terminal_artefacts("x\x08y\rz\x1b[0m\r\n"),
+        [
+            TerminalArtefact::Sgr,
+            TerminalArtefact::Backspace,
+            TerminalArtefact::CarriageReturn
+        ]
+    );
+    // CRLF line endings are not overwrites
+    assert!(detect_terminal_artefacts("a\r\nb\r\n").is_empty());
+}
+
+#[test]
+fn test_render_overwrites() {
+    assert_eq!(render_terminal("loading...\rdone\n"), "doneing...\n");
+    assert_eq!(render_terminal("abc\x08\x08\x08\x08X"), "Xbc");
+    assert_eq!(render_terminal("a\rb\nc\rd\n"), "b\nd\n");
+    // Unterminated sequences run to the end of the text
+    assert_eq!(strip_ansi("ok\x1b]0;never ends"), "ok");
+}
+
+#[test]
+fn test_usage_summary_colours_are_detected() {
+    let header = "\x1b[35m Usage Statistics\x1b[0m";
+    assert_eq!(detect_terminal_artefacts(header), [TerminalArtefact::Sgr]);
+    assert_eq!(strip_ansi(header), " Usage Statistics");
+}
+
+#[test]
+fn test_analysis_reports_terminal_artefacts() {
+    let analysis = CharCodeData::analyze("\x1b[32mlet x = 1;\x1b[0m", "let x = 1;");
+    assert_eq!(analysis.terminal_artefacts, [TerminalArtefact::Sgr]);
+    let top = &analysis.findings[0];
+    assert_eq!(top.kind, FindingKind::TerminalArtefacts);
+    assert!((top.confidence - 1.0).abs() < f64::EPSILON);
+    assert!(
+        analysis
+            .format_detailed_report()
+            .contains("Terminal artefacts: SGR colour/style code")
+    );
+}
+
+#[test]
+fn test_classic_mac_line_endings_are_not_overwrites() {
+    assert!(detect_terminal_artefacts("line one\rtwo\r").is_empty());
+    assert_eq!(render_terminal("line one\rtwo\r"), "line one\rtwo\r");
+    assert_eq!(render_terminal("a\rb\x08c\r"), "a\rc\r");
+    // A trailing CR has nothing to overwrite
+    assert!(detect_terminal_artefacts("done\n\r").is_empty());
+
+    let analysis = CharCodeData::analyze("fn a() {\r    x();\r}", "fn a() {\n    x();\n}");
+    assert!(analysis.terminal_artefacts.is_empty());
+    assert_eq!(
+        analysis.findings[0].kind,
+        FindingKind::Whitespace(WhitespaceIssue::MixedLineEndings)
+    );
+    assert!(
+        analysis
+            .findings
+            .iter()
+            .all(|finding| finding.kind != FindingKind::TerminalArtefacts)
+    );
+    let analysis = CharCodeData::analyze("a\rb", "a\rb\rc\r");
+    assert!(analysis.terminal_artefacts.is_empty());
+}
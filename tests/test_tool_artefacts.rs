@@ -0,0 +1,107 @@
+//! Tests for tool-output artefact detection and stripping
+
+use kodegen_utils::char_analysis::{CharCodeData, FindingKind};
+use kodegen_utils::tool_artefacts::{
+    ToolArtefact, detect_artefacts, strip_artefact, strip_artefacts,
+};
+
+#[test]
+fn test_line_number_gutters() {
+    for pasted in [
+        "  41 | fn main() {\n  42 |     run();",
+        "41\u{2192}fn main() {\n42\u{2192}    run();",
+        "    41\tfn main() {\n    42\t    run();",
+        "41: fn main() {\n42:     run();",
+    ] {
+        assert_eq!(
+            detect_artefacts(pasted),
+            [ToolArtefact::LineNumbers],
+            "{pasted:?}"
+        );
+        assert_eq!(
+            strip_artefacts(pasted),
+            "fn main() {\n    run();",
+            "{pasted:?}"
+        );
+    }
+
+    // Numbers must be consecutive, and a lone "1: x" is ambiguous
+    assert!(detect_artefacts("1: a\n3: b").is_empty());
+    assert!(detect_artefacts("1: value").is_empty());
+    assert!(detect_artefacts("2024\tTotal").is_empty());
+    assert_eq!(detect_artefacts("42 | x"), [ToolArtefact::LineNumbers]);
+}
+
+#[test]
+fn test_code_fences() {
+    let pasted = "```rust\nlet x = 1;\n```\n";
+    assert_eq!(detect_artefacts(pasted), [ToolArtefact::CodeFence]);
+    assert_eq!(strip_artefacts(pasted), "let x = 1;\n");
+    assert_eq!(strip_artefacts("~~~\nlet x = 1;\n~~~"), "let x = 1;");
+}
+
+#[test]
+fn test_diff_prefixes() {
+    let pasted = "@@ -1,3 +1,3 @@\n fn main() {\n-    old();\n+    new();\n }";
+    assert_eq!(detect_artefacts(pasted), [ToolArtefact::DiffPrefix]);
+    assert_eq!(strip_artefacts(pasted), "fn main() {\n    new();\n}");
+
+    // Only one side present: every line is kept
+    assert_eq!(
+        strip_artefact("-a();\n-b();", ToolArtefact::DiffPrefix),
+        "a();\nb();"
+    );
+
+    // Markdown lists and ordinary code are not diffs
+    assert!(detect_artefacts("- first\n- second").is_empty());
+    assert!(detect_artefacts("x = -1;\ny = +2;").is_empty());
+}
+
+#[test]
+fn test_truncation_markers() {
+    let pasted = "fn a() {}\n// ...\nfn z() {}";
+    assert_eq!(detect_artefacts(pasted), [ToolArtefact::Truncation]);
+    assert_eq!(strip_artefacts(pasted), "fn a() {}\nfn z() {}");
+
+    assert_eq!(
+        strip_artefacts("let x = compute(\u{2026}"),
+        "let x = compute("
+    );
+    assert_eq!(
+        detect_artefacts("a\n... (12 more lines)\n"),
+        [ToolArtefact::Truncation]
+    );
+
+    // Only the terminator the dropped marker line leaves behind is trimmed
+    assert_eq!(strip_artefact("a\n\n...", ToolArtefact::Truncation), "a\n");
+    assert_eq!(strip_artefact("a\r\n...", ToolArtefact::Truncation), "a");
+}
+
+#[test]
+fn test_combined_artefacts() {
+    let pasted = "```\n  10 | let a = 1;\n  11 | let b = 2;\n```";
+    assert_eq!(strip_artefacts(pasted), "let a = 1;\nlet b = 2;");
+}
+
+#[test]
+fn test_analysis_reports_artefacts() {
+    let analysis = CharCodeData::analyze("  7 | let a = 1;", "let a = 1;");
+    assert_eq!(analysis.tool_artefacts, [ToolArtefact::LineNumbers]);
+    let top = &analysis.findings[0];
+    assert_eq!(
+        top.kind,
+        FindingKind::ToolArtefact(ToolArtefact::LineNumbers)
+    );
+    assert!((top.confidence - 1.0).abs() < f64::EPSILON);
+    assert!(
+        analysis
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("line-number gutter copied from tool output")
+    );
+
+    // Artefacts also present in the file are not reported
+    let fenced = CharCodeData::analyze("```\nx\n```", "```\ny\n```");
+    assert!(fenced.tool_artefacts.is_empty());
+}